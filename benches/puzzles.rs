@@ -0,0 +1,102 @@
+use aoc2020::puzzle_registry::PuzzleRegistry;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::path::PathBuf;
+
+const YEAR: u16 = 2020;
+
+// Benchmarks the parse phase and each part of every registered puzzle separately, first against
+// the puzzle input in resources/tests and then against a much larger synthetic input (for the days
+// that have a generator).
+fn bench_puzzles(c: &mut Criterion) {
+    let registry = PuzzleRegistry::create();
+    for day in registry.days(YEAR) {
+        let constructor = registry.get(YEAR, day).unwrap();
+        let mut inputs = Vec::new();
+        if let Ok(input) = fs::read_to_string(PathBuf::from(format!("resources/tests/{}", day))) {
+            inputs.push(("puzzle", input));
+        }
+        if let Some(input) = synthetic_input(day) {
+            inputs.push(("synthetic", input));
+        }
+        let mut group = c.benchmark_group(format!("day{:02}", day));
+        for (name, input) in inputs.iter() {
+            if *name == "synthetic" {
+                // Each iteration takes a while on the large inputs, so take fewer samples
+                group.sample_size(10);
+            }
+            group.bench_with_input(BenchmarkId::new("parse", name), input, |b, input| {
+                b.iter(|| constructor(black_box(input)))
+            });
+            let puzzle = constructor(input).unwrap();
+            group.bench_with_input(BenchmarkId::new("part1", name), &puzzle, |b, puzzle| {
+                b.iter(|| puzzle.solve_part_1())
+            });
+            group.bench_with_input(BenchmarkId::new("part2", name), &puzzle, |b, puzzle| {
+                b.iter(|| puzzle.solve_part_2())
+            });
+        }
+        group.finish();
+    }
+}
+
+// Generates a large, well-formed input for the given day. The inputs don't necessarily have a
+// solution -- a solver that searches everything without finding one is a useful worst case.
+fn synthetic_input(day: u8) -> Option<String> {
+    let mut rng = StdRng::seed_from_u64(day as u64);
+    let lines: Vec<String> = match day {
+        1 => (0..20_000)
+            .map(|_| rng.gen_range(1, 2020).to_string())
+            .collect(),
+        2 => (0..50_000)
+            .map(|_| {
+                let lower = rng.gen_range(1, 10);
+                let upper = rng.gen_range(lower, 20);
+                let letter = rng.gen_range(b'a', b'f') as char;
+                let password: String = (0..rng.gen_range(upper, 30))
+                    .map(|_| rng.gen_range(b'a', b'f') as char)
+                    .collect();
+                format!("{}-{} {}: {}", lower, upper, letter, password)
+            })
+            .collect(),
+        3 => (0..10_000)
+            .map(|_| (0..1_000).map(|_| if rng.gen_bool(0.2) { '#' } else { '.' }).collect())
+            .collect(),
+        4 => (0..20_000)
+            .map(|_| {
+                let fields = [
+                    format!("byr:{}", rng.gen_range(1900, 2010)),
+                    format!("iyr:{}", rng.gen_range(2005, 2025)),
+                    format!("eyr:{}", rng.gen_range(2015, 2035)),
+                    format!("hgt:{}cm", rng.gen_range(140, 200)),
+                    format!("hcl:#{:06x}", rng.gen_range(0, 0x1000000)),
+                    "ecl:brn".to_string(),
+                    format!("pid:{:09}", rng.gen_range(0, 1_000_000_000)),
+                    format!("cid:{}", rng.gen_range(1, 400)),
+                ];
+                fields.iter()
+                    .filter(|_| rng.gen_bool(0.9))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ") + "\n"
+            })
+            .collect(),
+        5 => (0..50_000)
+            .map(|_| (0..10)
+                .map(|i| match (i < 7, rng.gen_bool(0.5)) {
+                    (true, true) => 'B',
+                    (true, false) => 'F',
+                    (false, true) => 'R',
+                    (false, false) => 'L',
+                })
+                .collect())
+            .collect(),
+        _ => return None,
+    };
+    Some(lines.join("\n"))
+}
+
+criterion_group!(benches, bench_puzzles);
+criterion_main!(benches);
@@ -0,0 +1,143 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Unsigned(u64),
+    Signed(i64),
+
+    // For answers that do not fit into 64 bits
+    Big(i128),
+
+    Text(String),
+
+    // Multi-line answers such as letters drawn on a grid (one string per row)
+    Grid(Vec<String>),
+}
+
+impl Answer {
+    // Returns the answer as an integer (if it is one) so that answers can be compared or combined
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Answer::Unsigned(n) => Some(*n as i128),
+            Answer::Signed(n) => Some(*n as i128),
+            Answer::Big(n) => Some(*n),
+            Answer::Text(_) | Answer::Grid(_) => None,
+        }
+    }
+
+    // Reads an answer typed in by hand (or stored as text), recognizing integers as such
+    pub fn parse(text: &str) -> Answer {
+        let text = text.trim();
+        if let Ok(n) = text.parse::<u64>() {
+            Answer::Unsigned(n)
+        } else if let Ok(n) = text.parse::<i64>() {
+            Answer::Signed(n)
+        } else if let Ok(n) = text.parse::<i128>() {
+            Answer::Big(n)
+        } else {
+            Answer::Text(text.to_string())
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Signed(n) => write!(f, "{}", n),
+            Answer::Big(n) => write!(f, "{}", n),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+macro_rules! answer_from {
+    ($variant:ident, $target:ty, $($source:ty),*) => {
+        $(impl From<$source> for Answer {
+            fn from(n: $source) -> Answer {
+                Answer::$variant(n as $target)
+            }
+        })*
+    };
+}
+
+answer_from!(Unsigned, u64, u8, u16, u32, u64, usize);
+answer_from!(Signed, i64, i8, i16, i32, i64, isize);
+answer_from!(Big, i128, i128);
+
+impl From<String> for Answer {
+    fn from(text: String) -> Answer {
+        Answer::Text(text)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(text: &str) -> Answer {
+        Answer::Text(text.to_string())
+    }
+}
+
+// Formats an answer for display next to a label. Grid answers start on their own line and are
+// indented so they stay readable when printed among other answers.
+pub fn format_answer(label: &str, answer: &Answer) -> String {
+    match answer {
+        Answer::Grid(rows) => {
+            let mut output = format!("{}:", label);
+            for row in rows {
+                output.push_str("\n    ");
+                output.push_str(row);
+            }
+            output
+        },
+        _ => format!("{}: {}", label, answer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answer::{Answer, format_answer};
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Answer::from(514579u32), Answer::Unsigned(514579));
+        assert_eq!(Answer::from(7usize), Answer::Unsigned(7));
+        assert_eq!(Answer::from(-3i32), Answer::Signed(-3));
+        assert_eq!(Answer::from(1i128 << 100), Answer::Big(1 << 100));
+        assert_eq!(Answer::from("abc"), Answer::Text("abc".to_string()));
+    }
+
+    #[test]
+    fn test_as_integer() {
+        let total: i128 = [Answer::Unsigned(3), Answer::Signed(-1), Answer::Big(10)].iter()
+            .filter_map(Answer::as_integer)
+            .sum();
+        assert_eq!(total, 12);
+        assert_eq!(Answer::Text("12".to_string()).as_integer(), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Answer::parse("514579"), Answer::Unsigned(514579));
+        assert_eq!(Answer::parse(" -3\n"), Answer::Signed(-3));
+        assert_eq!(Answer::parse("-18446744073709551616"), Answer::Big(-(u64::MAX as i128) - 1));
+        assert_eq!(Answer::parse("ABCDEFGH"), Answer::Text("ABCDEFGH".to_string()));
+        assert_eq!(Answer::parse("12a"), Answer::Text("12a".to_string()));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Answer::Unsigned(3510149120).to_string(), "3510149120");
+        assert_eq!(Answer::Signed(-42).to_string(), "-42");
+        assert_eq!(Answer::Big(u64::MAX as i128 + 1).to_string(), "18446744073709551616");
+        assert_eq!(Answer::Text("FOO".to_string()).to_string(), "FOO");
+        assert_eq!(Answer::Grid(vec!["#.".to_string(), ".#".to_string()]).to_string(), "#.\n.#");
+    }
+
+    #[test]
+    fn test_format_answer() {
+        assert_eq!(format_answer("Day 01 Part 1", &Answer::Unsigned(1)), "Day 01 Part 1: 1");
+        let grid = Answer::Grid(vec!["#..#".to_string(), "####".to_string()]);
+        assert_eq!(format_answer("Day 01 Part 2", &grid), "Day 01 Part 2:\n    #..#\n    ####");
+    }
+}
@@ -0,0 +1,304 @@
+use crate::answer::Answer;
+use crate::puzzle_input_fetcher::SubmissionOutcome;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// What the site said about an answer that was tried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Correct,
+    Wrong,
+    TooHigh,
+    TooLow,
+}
+
+impl Outcome {
+    pub fn parse(text: &str) -> Result<Outcome> {
+        match text {
+            "correct" => Ok(Outcome::Correct),
+            "wrong" => Ok(Outcome::Wrong),
+            "too-high" => Ok(Outcome::TooHigh),
+            "too-low" => Ok(Outcome::TooLow),
+            _ => Err(format!("'{}' is not a valid outcome. \
+                              Expected one of: correct, wrong, too-high, too-low", text)),
+        }
+    }
+
+    // Returns the outcome worth recording for a submission (if any). Submissions that were rate
+    // limited or made to an already solved part say nothing about the answer.
+    pub fn from_submission(outcome: SubmissionOutcome) -> Option<Outcome> {
+        match outcome {
+            SubmissionOutcome::Correct => Some(Outcome::Correct),
+            SubmissionOutcome::Wrong => Some(Outcome::Wrong),
+            SubmissionOutcome::TooHigh => Some(Outcome::TooHigh),
+            SubmissionOutcome::TooLow => Some(Outcome::TooLow),
+            SubmissionOutcome::RateLimited { .. } | SubmissionOutcome::AlreadySolved => None,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Correct => write!(f, "correct"),
+            Outcome::Wrong => write!(f, "wrong"),
+            Outcome::TooHigh => write!(f, "too-high"),
+            Outcome::TooLow => write!(f, "too-low"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub part: u8,
+    pub outcome: Outcome,
+    pub answer: Answer,
+}
+
+// Whether a candidate answer is worth submitting given the attempts recorded so far
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    // Nothing recorded rules the answer out
+    Untried,
+
+    // The answer has already been recorded as correct
+    KnownCorrect,
+
+    // A different answer has already been recorded as correct
+    AlreadySolved { correct: Answer },
+
+    // The same answer has already been recorded as wrong
+    KnownWrong,
+
+    // The answer is no lower than an answer recorded as too high
+    TooHigh { bound: Answer },
+
+    // The answer is no higher than an answer recorded as too low
+    TooLow { bound: Answer },
+}
+
+impl Verdict {
+    pub fn should_submit(&self) -> bool {
+        *self == Verdict::Untried
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Untried => write!(f, "Nothing recorded rules this answer out"),
+            Verdict::KnownCorrect => write!(f, "This answer is already known to be correct"),
+            Verdict::AlreadySolved { correct } => {
+                write!(f, "This part is already solved with a different answer: {}", correct)
+            },
+            Verdict::KnownWrong => write!(f, "This answer is already known to be wrong"),
+            Verdict::TooHigh { bound } => {
+                write!(f, "This answer is too high: {} was already too high", bound)
+            },
+            Verdict::TooLow { bound } => {
+                write!(f, "This answer is too low: {} was already too low", bound)
+            },
+        }
+    }
+}
+
+// Keeps track of the answers tried for each part of a puzzle and what the site said about them.
+// The attempts for a day are kept next to its puzzle input, e.g. 'puzzle/2020/1.answers' for day 1
+// in 2020, with one attempt per line.
+pub struct AnswerLedger {
+    // The location where puzzle input is stored (by default 'puzzle')
+    input_path: PathBuf,
+}
+
+impl AnswerLedger {
+    // Creates an AnswerLedger using the default puzzle input path
+    pub fn create() -> AnswerLedger {
+        AnswerLedger::create_custom(Path::new("puzzle"))
+    }
+
+    // Creates an AnswerLedger using the given puzzle input path. Used only for testing.
+    pub fn create_custom(input_path: &Path) -> AnswerLedger {
+        AnswerLedger {
+            input_path: input_path.to_path_buf(),
+        }
+    }
+
+    // Returns the recorded attempts for the given day in the order they were recorded
+    pub fn attempts(&self, year: u16, day: u8) -> Result<Vec<Attempt>> {
+        let path = self.ledger_path(year, day);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read answers from {}: {}", path.display(), e))?;
+        contents.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| parse_attempt(line).ok_or_else(|| {
+                format!("Failed to read answers from {}: Line {} is not valid: {}",
+                        path.display(), i + 1, line)
+            }))
+            .collect()
+    }
+
+    // Works out whether the answer is worth submitting for the given part
+    pub fn check(&self, year: u16, day: u8, part: u8, answer: &Answer) -> Result<Verdict> {
+        let attempts = self.attempts(year, day)?
+            .into_iter()
+            .filter(|attempt| attempt.part == part)
+            .collect::<Vec<_>>();
+        let text = answer.to_string();
+        if let Some(attempt) = attempts.iter().find(|a| a.outcome == Outcome::Correct) {
+            return if attempt.answer.to_string() == text {
+                Ok(Verdict::KnownCorrect)
+            } else {
+                Ok(Verdict::AlreadySolved { correct: attempt.answer.clone() })
+            };
+        }
+        if attempts.iter().any(|attempt| attempt.answer.to_string() == text) {
+            return Ok(Verdict::KnownWrong);
+        }
+        let n = match Answer::parse(&text).as_integer() {
+            Some(n) => n,
+            None => return Ok(Verdict::Untried),
+        };
+        // The tightest bound that rules the answer out (if any)
+        let bounds = |outcome| attempts.iter()
+            .filter(move |attempt| attempt.outcome == outcome)
+            .filter_map(|attempt| attempt.answer.as_integer().map(|bound| (bound, attempt)));
+        let too_high = bounds(Outcome::TooHigh)
+            .filter(|(bound, _)| n >= *bound)
+            .min_by_key(|(bound, _)| *bound);
+        if let Some((_, attempt)) = too_high {
+            return Ok(Verdict::TooHigh { bound: attempt.answer.clone() });
+        }
+        let too_low = bounds(Outcome::TooLow)
+            .filter(|(bound, _)| n <= *bound)
+            .max_by_key(|(bound, _)| *bound);
+        if let Some((_, attempt)) = too_low {
+            return Ok(Verdict::TooLow { bound: attempt.answer.clone() });
+        }
+        Ok(Verdict::Untried)
+    }
+
+    // Records what the site said about an answer for the given part
+    pub fn record(&self, year: u16, day: u8, part: u8, answer: &Answer, outcome: Outcome)
+        -> Result<()> {
+        if !(1..=2).contains(&part) {
+            return Err(format!("There is no part {} for {} day {}", part, year, day));
+        }
+        let text = answer.to_string();
+        if text.trim().is_empty() || text.contains('\n') {
+            return Err(format!("The answer '{}' can't be recorded", text));
+        }
+        let path = self.ledger_path(year, day);
+        fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|mut file| writeln!(file, "{} {} {}", part, outcome, text.trim()))
+            .map_err(|e| format!("Failed to record answer in {}: {}", path.display(), e))
+    }
+
+    fn ledger_path(&self, year: u16, day: u8) -> PathBuf {
+        self.input_path.join(year.to_string()).join(format!("{}.answers", day))
+    }
+}
+
+// Parses a line of the form '<part> <outcome> <answer>'
+fn parse_attempt(line: &str) -> Option<Attempt> {
+    let mut fields = line.trim().splitn(3, ' ');
+    let part = fields.next()?.parse::<u8>().ok().filter(|part| (1..=2).contains(part))?;
+    let outcome = Outcome::parse(fields.next()?).ok()?;
+    let answer = Answer::parse(fields.next()?);
+    Some(Attempt { part, outcome, answer })
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+#[cfg(test)]
+mod tests {
+    use crate::answer::Answer;
+    use crate::answer_ledger::{AnswerLedger, Attempt, Outcome, Verdict};
+
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_and_read_attempts() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let ledger = AnswerLedger::create_custom(puzzle_store_dir.path());
+        assert!(ledger.attempts(2020, 1).unwrap().is_empty());
+        ledger.record(2020, 1, 1, &Answer::Unsigned(100), Outcome::TooLow).unwrap();
+        ledger.record(2020, 1, 2, &Answer::from("ABC DEF"), Outcome::Wrong).unwrap();
+        ledger.record(2020, 1, 1, &Answer::Signed(-5), Outcome::Correct).unwrap();
+        assert_eq!(ledger.attempts(2020, 1).unwrap(), vec![
+            Attempt { part: 1, outcome: Outcome::TooLow, answer: Answer::Unsigned(100) },
+            Attempt { part: 2, outcome: Outcome::Wrong, answer: Answer::from("ABC DEF") },
+            Attempt { part: 1, outcome: Outcome::Correct, answer: Answer::Signed(-5) },
+        ]);
+        let contents = fs::read_to_string(puzzle_store_dir.path().join("2020/1.answers")).unwrap();
+        assert_eq!(contents, "1 too-low 100\n2 wrong ABC DEF\n1 correct -5\n");
+        assert!(ledger.attempts(2019, 1).unwrap().is_empty());
+        assert!(ledger.attempts(2020, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_rejects_bad_attempts() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let ledger = AnswerLedger::create_custom(puzzle_store_dir.path());
+        assert!(ledger.record(2020, 1, 3, &Answer::Unsigned(1), Outcome::Wrong).is_err());
+        assert!(ledger.record(2020, 1, 1, &Answer::from(" "), Outcome::Wrong).is_err());
+        let grid = Answer::Grid(vec!["#.".to_string(), ".#".to_string()]);
+        assert!(ledger.record(2020, 1, 1, &grid, Outcome::Wrong).is_err());
+        assert!(ledger.attempts(2020, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_ledger_is_an_error() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let ledger = AnswerLedger::create_custom(puzzle_store_dir.path());
+        fs::create_dir(puzzle_store_dir.path().join("2020")).unwrap();
+        fs::write(puzzle_store_dir.path().join("2020/1.answers"), "1 correct 5\n1 maybe 6\n")
+            .unwrap();
+        assert!(ledger.attempts(2020, 1).is_err());
+        assert!(ledger.check(2020, 1, 1, &Answer::Unsigned(6)).is_err());
+    }
+
+    #[test]
+    fn test_check_uses_bounds_and_known_answers() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let ledger = AnswerLedger::create_custom(puzzle_store_dir.path());
+        let check = |answer: Answer| ledger.check(2020, 3, 1, &answer).unwrap();
+        assert_eq!(check(Answer::Unsigned(50)), Verdict::Untried);
+        ledger.record(2020, 3, 1, &Answer::Unsigned(100), Outcome::TooHigh).unwrap();
+        ledger.record(2020, 3, 1, &Answer::Unsigned(80), Outcome::TooHigh).unwrap();
+        ledger.record(2020, 3, 1, &Answer::Unsigned(10), Outcome::TooLow).unwrap();
+        ledger.record(2020, 3, 1, &Answer::Unsigned(42), Outcome::Wrong).unwrap();
+        ledger.record(2020, 3, 2, &Answer::Unsigned(7), Outcome::Correct).unwrap();
+        assert_eq!(check(Answer::Unsigned(50)), Verdict::Untried);
+        assert_eq!(check(Answer::from("50")), Verdict::Untried);
+        assert_eq!(check(Answer::Unsigned(42)), Verdict::KnownWrong);
+        assert_eq!(check(Answer::from("42")), Verdict::KnownWrong);
+        assert_eq!(check(Answer::Unsigned(80)), Verdict::KnownWrong);
+        assert_eq!(check(Answer::Unsigned(90)), Verdict::TooHigh { bound: Answer::Unsigned(80) });
+        assert_eq!(check(Answer::Big(1 << 100)), Verdict::TooHigh { bound: Answer::Unsigned(80) });
+        assert_eq!(check(Answer::Signed(-1)), Verdict::TooLow { bound: Answer::Unsigned(10) });
+        assert_eq!(check(Answer::from("ABC")), Verdict::Untried);
+        assert!(!check(Answer::Unsigned(90)).should_submit());
+        assert!(check(Answer::Unsigned(50)).should_submit());
+        ledger.record(2020, 3, 1, &Answer::Unsigned(55), Outcome::Correct).unwrap();
+        assert_eq!(check(Answer::Unsigned(55)), Verdict::KnownCorrect);
+        assert_eq!(check(Answer::Unsigned(50)),
+                   Verdict::AlreadySolved { correct: Answer::Unsigned(55) });
+        assert_eq!(ledger.check(2020, 3, 2, &Answer::Unsigned(7)).unwrap(), Verdict::KnownCorrect);
+    }
+
+    #[test]
+    fn test_outcome_parsing() {
+        for outcome in &[Outcome::Correct, Outcome::Wrong, Outcome::TooHigh, Outcome::TooLow] {
+            assert_eq!(Outcome::parse(&outcome.to_string()).unwrap(), *outcome);
+        }
+        assert!(Outcome::parse("high").is_err());
+    }
+}
@@ -0,0 +1,837 @@
+use crate::answer_ledger::Outcome;
+use crate::fetch_error::FetchError;
+use crate::puzzle_input_fetcher::FetchPolicy;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+// The year used when none is selected
+pub const DEFAULT_YEAR: u16 = 2020;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    // Solves the selected days (all available days if none are selected) and parts
+    Run(RunOptions),
+
+    // Fetches the puzzle input for the selected days without solving them, waiting for each day to
+    // unlock if asked to
+    Fetch { year: u16, days: Option<Vec<u8>>, wait: bool },
+
+    // Checks the stored puzzle input for the selected days, downloading bad inputs again if asked
+    // to
+    Verify { year: u16, days: Option<Vec<u8>>, refetch: bool },
+
+    // Lists the days of the selected year that have a solution
+    List { year: u16 },
+
+    // Submits an answer for one part of a day. Without an answer, the part is solved first and
+    // its answer is submitted.
+    Submit { year: u16, day: u8, part: u8, answer: Option<String> },
+
+    // Checks an answer for one part of a day against the answers tried so far
+    Check { year: u16, day: u8, part: u8, answer: String },
+
+    // Records what the site said about an answer that was submitted by hand
+    Record { year: u16, day: u8, part: u8, answer: String, outcome: Outcome },
+
+    // Shows the description of a puzzle (or only the examples in it)
+    Describe { year: u16, day: u8, format: DescriptionFormat },
+
+    // Checks that the session token works and shows the account it belongs to
+    Whoami,
+
+    // Shows the standings of a private leaderboard and when its members solved the selected days
+    // (all days anyone has solved if none are selected)
+    Leaderboard { year: u16, id: u64, days: Option<Vec<u8>> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DescriptionFormat {
+    Markdown,
+    Text,
+
+    // The example inputs followed by the highlighted answers
+    Examples,
+}
+
+// Where to read the puzzle input for a single day from, instead of the local store or the site
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputFile {
+    Stdin,
+    Path(PathBuf),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+    pub year: u16,
+    pub days: Option<Vec<u8>>,
+    pub parts: Vec<u8>,
+
+    // If set, each phase is timed over the given number of iterations and a report is printed
+    pub iterations: Option<usize>,
+
+    // How many days to fetch and solve at the same time
+    pub jobs: usize,
+
+    // Whether to wait for days that haven't unlocked yet instead of failing on them
+    pub wait: bool,
+
+    // If set, the input for the selected day is read from here and nothing is fetched
+    pub input: Option<InputFile>,
+
+    // Whether to also remove trailing whitespace from every line of the puzzle input, and to
+    // report the changes made when normalizing it
+    pub strict_input: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> RunOptions {
+        RunOptions {
+            year: DEFAULT_YEAR,
+            days: None,
+            parts: vec![1, 2],
+            iterations: None,
+            jobs: 1,
+            wait: false,
+            input: None,
+            strict_input: false,
+        }
+    }
+}
+
+// The parsed command line
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub command: Command,
+
+    // A session token to use ahead of any other
+    pub session_token: Option<String>,
+
+    // When the site may be used for what is (or could be) in the local store
+    pub fetch_policy: FetchPolicy,
+}
+
+// Parses the command line arguments into a Command (along with the options that apply to every
+// command). Running without a subcommand is the same as 'run' with no flags (i.e. solve every
+// available day).
+pub fn parse_args<I, T>(args: I) -> clap::Result<Args>
+    where I: IntoIterator<Item = T>, T: Into<OsString> + Clone {
+    let matches = app().get_matches_from_safe(args)?;
+    let command = match matches.subcommand() {
+        ("run", Some(sub_matches)) => Command::Run(RunOptions {
+            year: selected_year(sub_matches),
+            days: selected_days(sub_matches),
+            parts: selected_parts(sub_matches),
+            iterations: selected_iterations(sub_matches),
+            jobs: sub_matches.value_of("jobs").map_or(1, |value| parse_jobs(value).unwrap()),
+            wait: sub_matches.is_present("wait"),
+            input: sub_matches.value_of_os("input").map(|value| match value.to_str() {
+                Some("-") => InputFile::Stdin,
+                _ => InputFile::Path(PathBuf::from(value)),
+            }),
+            strict_input: sub_matches.is_present("strict_input"),
+        }),
+        ("fetch", Some(sub_matches)) => Command::Fetch {
+            year: selected_year(sub_matches),
+            days: selected_days(sub_matches),
+            wait: sub_matches.is_present("wait"),
+        },
+        ("verify", Some(sub_matches)) => Command::Verify {
+            year: selected_year(sub_matches),
+            days: selected_days(sub_matches),
+            refetch: sub_matches.is_present("refetch"),
+        },
+        ("list", Some(sub_matches)) => Command::List {
+            year: selected_year(sub_matches),
+        },
+        ("submit", Some(sub_matches)) => Command::Submit {
+            year: selected_year(sub_matches),
+            day: parse_day(sub_matches.value_of("day").unwrap()).unwrap(),
+            part: selected_parts(sub_matches)[0],
+            answer: sub_matches.value_of("answer").map(|answer| answer.to_string()),
+        },
+        ("check", Some(sub_matches)) => Command::Check {
+            year: selected_year(sub_matches),
+            day: parse_day(sub_matches.value_of("day").unwrap()).unwrap(),
+            part: selected_parts(sub_matches)[0],
+            answer: sub_matches.value_of("answer").unwrap().to_string(),
+        },
+        ("record", Some(sub_matches)) => Command::Record {
+            year: selected_year(sub_matches),
+            day: parse_day(sub_matches.value_of("day").unwrap()).unwrap(),
+            part: selected_parts(sub_matches)[0],
+            answer: sub_matches.value_of("answer").unwrap().to_string(),
+            outcome: Outcome::parse(sub_matches.value_of("outcome").unwrap()).unwrap(),
+        },
+        ("describe", Some(sub_matches)) => Command::Describe {
+            year: selected_year(sub_matches),
+            day: parse_day(sub_matches.value_of("day").unwrap()).unwrap(),
+            format: if sub_matches.is_present("text") {
+                DescriptionFormat::Text
+            } else if sub_matches.is_present("examples") {
+                DescriptionFormat::Examples
+            } else {
+                DescriptionFormat::Markdown
+            },
+        },
+        ("whoami", Some(_)) => Command::Whoami,
+        ("leaderboard", Some(sub_matches)) => Command::Leaderboard {
+            year: selected_year(sub_matches),
+            id: parse_leaderboard_id(sub_matches.value_of("id").unwrap()).unwrap(),
+            days: selected_days(sub_matches),
+        },
+        _ => Command::Run(RunOptions::default()),
+    };
+    Ok(Args {
+        command,
+        session_token: matches.value_of("session_token").map(|token| token.to_string()),
+        fetch_policy: if matches.is_present("offline") {
+            FetchPolicy::OfflineOnly
+        } else {
+            matches.value_of("fetch_policy").map_or(FetchPolicy::default(),
+                                                    |value| parse_fetch_policy(value).unwrap())
+        },
+    })
+}
+
+// Returns the error followed by advice on how to get past it (if there is any)
+pub fn explain(error: &FetchError) -> String {
+    match advice(error) {
+        Some(advice) => format!("{}\nHint: {}", error, advice),
+        None => error.to_string(),
+    }
+}
+
+fn advice(error: &FetchError) -> Option<&'static str> {
+    match error {
+        FetchError::MissingToken { .. } => {
+            Some("Log in to https://adventofcode.com and copy the value of the 'session' cookie \
+                  into cookie.txt (or into the AOC_SESSION environment variable, or pass it with \
+                  --session-token)")
+        },
+        FetchError::BadTokenFormat { .. } => {
+            Some("Make sure to copy the whole value of the 'session' cookie and nothing else")
+        },
+        FetchError::Unauthorized { .. } => {
+            Some("The session token has probably expired. Log in again, copy the new value of the \
+                  'session' cookie and run 'whoami' to check it")
+        },
+        FetchError::NotYetUnlocked { .. } => Some("Use --wait to wait for the puzzle to unlock"),
+        FetchError::HttpStatus { status, .. } if status.is_server_error() => {
+            Some("The site is having trouble. Try again later")
+        },
+        FetchError::Transport { .. } => Some("Check your internet connection"),
+        FetchError::Io { .. } => Some("Check that the file named above exists and can be read"),
+        FetchError::CorruptLocalInput { .. } => {
+            Some("Run 'verify --refetch' to download it again")
+        },
+        FetchError::Offline { .. } => {
+            Some("Run without --offline (or with --fetch-policy cache) to use the site")
+        },
+        _ => None,
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("aoc2020")
+        .about("Advent of Code 2020 solutions")
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("session_token")
+            .long("session-token")
+            .takes_value(true)
+            .global(true)
+            .help("The session token to use (by default it is read from the AOC_SESSION \
+                   environment variable, ~/.config/aoc/session or cookie.txt)"))
+        .arg(Arg::with_name("fetch_policy")
+            .long("fetch-policy")
+            .takes_value(true)
+            .global(true)
+            .possible_values(&["offline", "cache", "refresh"])
+            .help("When to go to the site: 'offline' never does, 'cache' (the default) only does \
+                   for what isn't stored locally and 'refresh' downloads everything again"))
+        .arg(Arg::with_name("offline")
+            .long("offline")
+            .global(true)
+            .conflicts_with("fetch_policy")
+            .help("Only use what is stored locally (the same as --fetch-policy offline)"))
+        .subcommand(SubCommand::with_name("run")
+            .about("Solves the puzzles for the selected days")
+            .arg(year_arg())
+            .arg(day_arg())
+            .arg(days_arg())
+            .arg(part_arg()
+                .help("Only solve the given part"))
+            .arg(Arg::with_name("time")
+                .long("time")
+                .short("t")
+                .help("Time fetching, parsing and solving each day and print a report"))
+            .arg(Arg::with_name("iterations")
+                .long("iterations")
+                .takes_value(true)
+                .requires("time")
+                .validator(|value| parse_iterations(&value).map(|_| ()))
+                .help("How many times to repeat each timed phase (default: 10)"))
+            .arg(Arg::with_name("jobs")
+                .long("jobs")
+                .short("j")
+                .takes_value(true)
+                .validator(|value| parse_jobs(&value).map(|_| ()))
+                .help("Fetch and solve up to this many days in parallel (default: 1)"))
+            .arg(wait_arg())
+            .arg(Arg::with_name("input")
+                .long("input")
+                .short("i")
+                .takes_value(true)
+                .requires("day")
+                .conflicts_with("wait")
+                .help("Read the puzzle input for the selected day from this file ('-' for \
+                       standard input) instead of the local store or the site"))
+            .arg(Arg::with_name("strict_input")
+                .long("strict-input")
+                .help("Also remove trailing whitespace from every line of the puzzle input, and \
+                       report what had to be changed to bring it into the expected form (Unix \
+                       line endings, no byte order mark and a single newline at the end)")))
+        .subcommand(SubCommand::with_name("fetch")
+            .about("Fetches the puzzle input for the selected days")
+            .arg(year_arg())
+            .arg(day_arg())
+            .arg(days_arg())
+            .arg(wait_arg()))
+        .subcommand(SubCommand::with_name("verify")
+            .about("Checks the stored puzzle input for the selected days")
+            .arg(year_arg())
+            .arg(day_arg())
+            .arg(days_arg())
+            .arg(Arg::with_name("refetch")
+                .long("refetch")
+                .help("Download corrupt or suspicious puzzle input again")))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists the days that have a solution")
+            .arg(year_arg()))
+        .subcommand(SubCommand::with_name("submit")
+            .about("Submits an answer for one part of a puzzle")
+            .arg(year_arg())
+            .arg(day_arg()
+                .required(true))
+            .arg(part_arg()
+                .required(true)
+                .help("The part to submit the answer for"))
+            .arg(Arg::with_name("answer")
+                .help("The answer to submit (by default the answer is solved for)")))
+        .subcommand(SubCommand::with_name("check")
+            .about("Checks an answer against the answers already tried for that part")
+            .arg(year_arg())
+            .arg(day_arg()
+                .required(true))
+            .arg(part_arg()
+                .required(true)
+                .help("The part to check the answer for"))
+            .arg(Arg::with_name("answer")
+                .required(true)
+                .help("The answer to check")))
+        .subcommand(SubCommand::with_name("record")
+            .about("Records what the site said about an answer submitted by hand")
+            .arg(year_arg())
+            .arg(day_arg()
+                .required(true))
+            .arg(part_arg()
+                .required(true)
+                .help("The part the answer was submitted for"))
+            .arg(Arg::with_name("outcome")
+                .long("outcome")
+                .short("o")
+                .takes_value(true)
+                .required(true)
+                .possible_values(&["correct", "wrong", "too-high", "too-low"])
+                .help("What the site said about the answer"))
+            .arg(Arg::with_name("answer")
+                .required(true)
+                .help("The answer that was submitted")))
+        .subcommand(SubCommand::with_name("describe")
+            .about("Shows the description of a puzzle as Markdown")
+            .arg(year_arg())
+            .arg(day_arg()
+                .required(true))
+            .arg(Arg::with_name("text")
+                .long("text")
+                .conflicts_with("examples")
+                .help("Show the description as plain text"))
+            .arg(Arg::with_name("examples")
+                .long("examples")
+                .help("Only show the examples and the highlighted answers")))
+        .subcommand(SubCommand::with_name("whoami")
+            .alias("check-session")
+            .about("Checks that the session token works and shows the account it belongs to"))
+        .subcommand(SubCommand::with_name("leaderboard")
+            .about("Shows the standings of a private leaderboard and when each day was solved")
+            .arg(year_arg())
+            .arg(day_arg())
+            .arg(days_arg())
+            .arg(Arg::with_name("id")
+                .long("id")
+                .takes_value(true)
+                .required(true)
+                .validator(|value| parse_leaderboard_id(&value).map(|_| ()))
+                .help("The id of the leaderboard (the number at the end of its URL)")))
+}
+
+fn wait_arg() -> Arg<'static, 'static> {
+    Arg::with_name("wait")
+        .long("wait")
+        .short("w")
+        .help("Wait for puzzles that haven't unlocked yet")
+}
+
+fn part_arg() -> Arg<'static, 'static> {
+    Arg::with_name("part")
+        .long("part")
+        .short("p")
+        .takes_value(true)
+        .possible_values(&["1", "2"])
+}
+
+fn year_arg() -> Arg<'static, 'static> {
+    Arg::with_name("year")
+        .long("year")
+        .short("y")
+        .takes_value(true)
+        .validator(|value| parse_year(&value).map(|_| ()))
+        .help("Select the year of the puzzles (default: 2020)")
+}
+
+fn day_arg() -> Arg<'static, 'static> {
+    Arg::with_name("day")
+        .long("day")
+        .short("d")
+        .takes_value(true)
+        .conflicts_with("days")
+        .validator(|value| parse_day(&value).map(|_| ()))
+        .help("Select a single day (e.g. 3)")
+}
+
+fn days_arg() -> Arg<'static, 'static> {
+    Arg::with_name("days")
+        .long("days")
+        .takes_value(true)
+        .validator(|value| parse_days(&value).map(|_| ()))
+        .help("Select several days (e.g. 1..=5, 1..6 or 1,3,5)")
+}
+
+fn selected_year(matches: &ArgMatches) -> u16 {
+    matches.value_of("year").map_or(DEFAULT_YEAR, |value| parse_year(value).unwrap())
+}
+
+fn selected_days(matches: &ArgMatches) -> Option<Vec<u8>> {
+    // Both values have already been checked by their validators
+    match matches.value_of("day") {
+        Some(day) => Some(vec![parse_day(day).unwrap()]),
+        None => matches.value_of("days").map(|days| parse_days(days).unwrap()),
+    }
+}
+
+fn selected_parts(matches: &ArgMatches) -> Vec<u8> {
+    match matches.value_of("part") {
+        Some(part) => vec![part.parse::<u8>().unwrap()],
+        None => vec![1, 2],
+    }
+}
+
+fn selected_iterations(matches: &ArgMatches) -> Option<usize> {
+    if matches.is_present("time") {
+        Some(matches.value_of("iterations").map_or(10, |value| parse_iterations(value).unwrap()))
+    } else {
+        None
+    }
+}
+
+fn parse_iterations(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(iterations) if iterations > 0 => Ok(iterations),
+        _ => Err(format!("'{}' is not a valid number of iterations", value)),
+    }
+}
+
+fn parse_jobs(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("'{}' is not a valid number of jobs", value)),
+    }
+}
+
+fn parse_fetch_policy(value: &str) -> Result<FetchPolicy, String> {
+    match value {
+        "offline" => Ok(FetchPolicy::OfflineOnly),
+        "cache" => Ok(FetchPolicy::CacheThenRemote),
+        "refresh" => Ok(FetchPolicy::RemoteRefresh),
+        _ => Err(format!("'{}' is not a valid fetch policy", value)),
+    }
+}
+
+fn parse_leaderboard_id(value: &str) -> Result<u64, String> {
+    value.trim().parse::<u64>()
+        .map_err(|_| format!("'{}' is not a valid leaderboard id", value))
+}
+
+fn parse_year(value: &str) -> Result<u16, String> {
+    match value.trim().parse::<u16>() {
+        Ok(year) if year >= 2015 => Ok(year),
+        _ => Err(format!("'{}' is not a valid year. Advent of Code started in 2015.", value)),
+    }
+}
+
+fn parse_day(value: &str) -> Result<u8, String> {
+    match value.trim().parse::<u8>() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("'{}' is not a valid day. Expected a number from 1 to 25.", value)),
+    }
+}
+
+// Parses a selection of days. Accepts a comma separated list where each entry is either a single
+// day, an exclusive range (e.g. 1..6) or an inclusive range (e.g. 1..=5).
+fn parse_days(value: &str) -> Result<Vec<u8>, String> {
+    let mut days = Vec::new();
+    for entry in value.split(',') {
+        if let Some(index) = entry.find("..=") {
+            let start = parse_day(&entry[..index])?;
+            let end = parse_day(&entry[(index + 3)..])?;
+            days.extend(start..=end);
+        } else if let Some(index) = entry.find("..") {
+            let start = parse_day(&entry[..index])?;
+            let end = parse_day(&entry[(index + 2)..])?;
+            days.extend(start..end);
+        } else {
+            days.push(parse_day(entry)?);
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    if days.is_empty() {
+        Err(format!("'{}' does not select any days", value))
+    } else {
+        Ok(days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answer_ledger::Outcome;
+    use crate::cli::{explain, parse_args, parse_days, Command, DescriptionFormat, InputFile,
+                     RunOptions};
+    use crate::fetch_error::FetchError;
+    use crate::input_metadata::InputStatus;
+    use crate::puzzle_input_fetcher::FetchPolicy;
+    use crate::session_token::TokenSource;
+    use reqwest::StatusCode;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn parse_command(args: Vec<&str>) -> clap::Result<Command> {
+        parse_args(args).map(|args| args.command)
+    }
+
+    #[test]
+    fn test_no_subcommand_runs_everything() {
+        let command = parse_command(vec!["aoc2020"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions::default()));
+    }
+
+    #[test]
+    fn test_run_single_day_and_part() {
+        let command = parse_command(vec!["aoc2020", "run", "--day", "3", "--part", "2"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            days: Some(vec![3]),
+            parts: vec![2],
+            ..RunOptions::default()
+        }));
+    }
+
+    #[test]
+    fn test_run_day_range() {
+        let command = parse_command(vec!["aoc2020", "run", "--days", "1..=5"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            days: Some(vec![1, 2, 3, 4, 5]),
+            ..RunOptions::default()
+        }));
+    }
+
+    #[test]
+    fn test_run_with_timing() {
+        let command = parse_command(vec!["aoc2020", "run", "--time"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            iterations: Some(10),
+            ..RunOptions::default()
+        }));
+        let command = parse_command(vec!["aoc2020", "run", "--time", "--iterations", "3"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            iterations: Some(3),
+            ..RunOptions::default()
+        }));
+        assert!(parse_command(vec!["aoc2020", "run", "--iterations", "3"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--time", "--iterations", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_run_with_jobs() {
+        let command = parse_command(vec!["aoc2020", "run", "--jobs", "4"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            jobs: 4,
+            ..RunOptions::default()
+        }));
+        assert!(parse_command(vec!["aoc2020", "run", "--jobs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_run_with_wait() {
+        let command = parse_command(vec!["aoc2020", "run", "--day", "6", "--wait"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            days: Some(vec![6]),
+            wait: true,
+            ..RunOptions::default()
+        }));
+    }
+
+    #[test]
+    fn test_run_with_input() {
+        let command = parse_command(vec!["aoc2020", "run", "--day", "4", "--input", "my/4.txt"])
+            .unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            days: Some(vec![4]),
+            input: Some(InputFile::Path(PathBuf::from("my/4.txt"))),
+            ..RunOptions::default()
+        }));
+        let command = parse_command(vec!["aoc2020", "run", "-d", "4", "-i", "-"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            days: Some(vec![4]),
+            input: Some(InputFile::Stdin),
+            ..RunOptions::default()
+        }));
+        assert!(parse_command(vec!["aoc2020", "run", "--input", "-"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--days", "1..=2", "--input", "-"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "-d", "4", "-i", "-", "--wait"]).is_err());
+    }
+
+    #[test]
+    fn test_strict_input() {
+        assert_eq!(parse_command(vec!["aoc2020", "run", "--strict-input"]).unwrap(),
+                   Command::Run(RunOptions {
+                       strict_input: true,
+                       ..RunOptions::default()
+                   }));
+        assert_eq!(parse_command(vec!["aoc2020", "run"]).unwrap(),
+                   Command::Run(RunOptions::default()));
+        // Only run reports what was normalized, so it is the only command with a strict mode
+        assert!(parse_command(vec!["aoc2020", "fetch", "--strict-input"]).is_err());
+    }
+
+    #[test]
+    fn test_fetch_and_list() {
+        let command = parse_command(vec!["aoc2020", "fetch", "--days", "2,4"]).unwrap();
+        assert_eq!(command, Command::Fetch { year: 2020, days: Some(vec![2, 4]), wait: false });
+        let command = parse_command(vec!["aoc2020", "fetch", "--day", "2", "--wait"]).unwrap();
+        assert_eq!(command, Command::Fetch { year: 2020, days: Some(vec![2]), wait: true });
+        let command = parse_command(vec!["aoc2020", "list"]).unwrap();
+        assert_eq!(command, Command::List { year: 2020 });
+    }
+
+    #[test]
+    fn test_verify() {
+        let command = parse_command(vec!["aoc2020", "verify"]).unwrap();
+        assert_eq!(command, Command::Verify { year: 2020, days: None, refetch: false });
+        let command = parse_command(vec!["aoc2020", "verify", "-y", "2019", "--days", "1..3",
+                                      "--refetch"]).unwrap();
+        assert_eq!(command, Command::Verify { year: 2019, days: Some(vec![1, 2]), refetch: true });
+    }
+
+    #[test]
+    fn test_year_selection() {
+        let command = parse_command(vec!["aoc2020", "run", "--year", "2019", "--day", "1"]).unwrap();
+        assert_eq!(command, Command::Run(RunOptions {
+            year: 2019,
+            days: Some(vec![1]),
+            ..RunOptions::default()
+        }));
+        let command = parse_command(vec!["aoc2020", "fetch", "-y", "2015"]).unwrap();
+        assert_eq!(command, Command::Fetch { year: 2015, days: None, wait: false });
+        let command = parse_command(vec!["aoc2020", "list", "--year", "2018"]).unwrap();
+        assert_eq!(command, Command::List { year: 2018 });
+        assert!(parse_command(vec!["aoc2020", "run", "--year", "2014"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--year", "20"]).is_err());
+    }
+
+    #[test]
+    fn test_submit() {
+        let command = parse_command(vec!["aoc2020", "submit", "-d", "4", "-p", "2", "123"]).unwrap();
+        assert_eq!(command, Command::Submit {
+            year: 2020,
+            day: 4,
+            part: 2,
+            answer: Some("123".to_string()),
+        });
+        let command = parse_command(vec!["aoc2020", "submit", "-y", "2019", "-d", "4", "-p", "1"])
+            .unwrap();
+        assert_eq!(command, Command::Submit { year: 2019, day: 4, part: 1, answer: None });
+        assert!(parse_command(vec!["aoc2020", "submit", "--day", "4", "123"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "submit", "--part", "1", "123"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "submit", "--days", "1..3", "-p", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_check_and_record() {
+        let command = parse_command(vec!["aoc2020", "check", "-d", "4", "-p", "2", "123"]).unwrap();
+        assert_eq!(command, Command::Check {
+            year: 2020,
+            day: 4,
+            part: 2,
+            answer: "123".to_string(),
+        });
+        let command = parse_command(vec!["aoc2020", "record", "-d", "4", "-p", "1",
+                                      "--outcome", "too-high", "123"]).unwrap();
+        assert_eq!(command, Command::Record {
+            year: 2020,
+            day: 4,
+            part: 1,
+            answer: "123".to_string(),
+            outcome: Outcome::TooHigh,
+        });
+        assert!(parse_command(vec!["aoc2020", "check", "-d", "4", "-p", "2"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "record", "-d", "4", "-p", "1", "123"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "record", "-d", "4", "-p", "1",
+                                "--outcome", "high", "123"]).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        let command = parse_command(vec!["aoc2020", "describe", "-d", "4"]).unwrap();
+        assert_eq!(command, Command::Describe {
+            year: 2020,
+            day: 4,
+            format: DescriptionFormat::Markdown,
+        });
+        let command = parse_command(vec!["aoc2020", "describe", "-d", "4", "--text"]).unwrap();
+        assert_eq!(command, Command::Describe {
+            year: 2020,
+            day: 4,
+            format: DescriptionFormat::Text,
+        });
+        let command = parse_command(vec!["aoc2020", "describe", "-d", "4", "--examples"]).unwrap();
+        assert_eq!(command, Command::Describe {
+            year: 2020,
+            day: 4,
+            format: DescriptionFormat::Examples,
+        });
+        assert!(parse_command(vec!["aoc2020", "describe"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "describe", "-d", "4", "--text", "--examples"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_whoami() {
+        assert_eq!(parse_command(vec!["aoc2020", "whoami"]).unwrap(), Command::Whoami);
+        assert_eq!(parse_command(vec!["aoc2020", "check-session"]).unwrap(), Command::Whoami);
+        assert!(parse_command(vec!["aoc2020", "whoami", "--day", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let command = parse_command(vec!["aoc2020", "leaderboard", "--id", "123"]).unwrap();
+        assert_eq!(command, Command::Leaderboard { year: 2020, id: 123, days: None });
+        let command = parse_command(vec!["aoc2020", "leaderboard", "--id", "123", "-y", "2019",
+                                      "-d", "4"]).unwrap();
+        assert_eq!(command, Command::Leaderboard { year: 2019, id: 123, days: Some(vec![4]) });
+        assert!(parse_command(vec!["aoc2020", "leaderboard"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "leaderboard", "--id", "abc"]).is_err());
+    }
+
+    #[test]
+    fn test_session_token() {
+        let args = parse_args(vec!["aoc2020", "fetch", "--session-token", "abc"]).unwrap();
+        assert_eq!(args.session_token, Some("abc".to_string()));
+        let args = parse_args(vec!["aoc2020", "--session-token", "abc", "list"]).unwrap();
+        assert_eq!(args.session_token, Some("abc".to_string()));
+        assert_eq!(args.command, Command::List { year: 2020 });
+        let args = parse_args(vec!["aoc2020", "list"]).unwrap();
+        assert_eq!(args.session_token, None);
+    }
+
+    #[test]
+    fn test_fetch_policy() {
+        let fetch_policy = |args: Vec<&str>| parse_args(args).map(|args| args.fetch_policy);
+        assert_eq!(fetch_policy(vec!["aoc2020", "run"]).unwrap(), FetchPolicy::CacheThenRemote);
+        assert_eq!(fetch_policy(vec!["aoc2020", "--offline", "run"]).unwrap(),
+                   FetchPolicy::OfflineOnly);
+        assert_eq!(fetch_policy(vec!["aoc2020", "run", "--offline"]).unwrap(),
+                   FetchPolicy::OfflineOnly);
+        assert_eq!(fetch_policy(vec!["aoc2020", "fetch", "--fetch-policy", "offline"]).unwrap(),
+                   FetchPolicy::OfflineOnly);
+        assert_eq!(fetch_policy(vec!["aoc2020", "fetch", "--fetch-policy", "cache"]).unwrap(),
+                   FetchPolicy::CacheThenRemote);
+        assert_eq!(fetch_policy(vec!["aoc2020", "fetch", "--fetch-policy", "refresh"]).unwrap(),
+                   FetchPolicy::RemoteRefresh);
+        assert!(fetch_policy(vec!["aoc2020", "fetch", "--fetch-policy", "never"]).is_err());
+        assert!(fetch_policy(vec!["aoc2020", "fetch", "--offline", "--fetch-policy", "refresh"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_arguments_are_rejected() {
+        assert!(parse_command(vec!["aoc2020", "run", "--day", "0"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--day", "26"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--day", "x"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--part", "3"]).is_err());
+        assert!(parse_command(vec!["aoc2020", "run", "--day", "1", "--days", "1..3"]).is_err());
+    }
+
+    #[test]
+    fn test_explain() {
+        let remaining = Duration::from_secs(5);
+        let error = FetchError::NotYetUnlocked { year: 2020, day: 6, remaining };
+        assert_eq!(explain(&error), "The puzzle for 2020 day 6 unlocks in 5s\n\
+                                     Hint: Use --wait to wait for the puzzle to unlock");
+        let error = FetchError::Unauthorized {
+            context: "Failed to fetch remote puzzle input for 2020 day 1".to_string(),
+            token_source: TokenSource::Flag,
+        };
+        assert!(explain(&error).contains("\nHint: The session token has probably expired."));
+        let error = FetchError::MissingToken { looked_in: vec![TokenSource::Flag] };
+        assert!(explain(&error).contains("\nHint: Log in to https://adventofcode.com"));
+        let error = FetchError::HttpStatus {
+            context: "Failed".to_string(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+        };
+        assert!(explain(&error).ends_with("\nHint: The site is having trouble. Try again later"));
+        let error = FetchError::HttpStatus {
+            context: "Failed".to_string(),
+            status: StatusCode::NOT_FOUND,
+        };
+        assert_eq!(explain(&error), "Failed: Got status code = 404 Not Found");
+        let error = FetchError::CorruptLocalInput {
+            year: 2020,
+            day: 1,
+            path: PathBuf::from("puzzle/2020/1"),
+            status: InputStatus::Corrupt("the SHA-256 does not match".to_string()),
+        };
+        assert_eq!(explain(&error), "The puzzle input for 2020 day 1 in the local store \
+                                     (puzzle/2020/1) is corrupt: the SHA-256 does not match\n\
+                                     Hint: Run 'verify --refetch' to download it again");
+        let error = FetchError::Offline {
+            what: "The puzzle input for 2020 day 1".to_string(),
+            path: Some(PathBuf::from("puzzle/2020/1")),
+        };
+        assert_eq!(explain(&error), "The puzzle input for 2020 day 1 is not in the local store \
+                                     (expected at puzzle/2020/1) and fetching is turned off\n\
+                                     Hint: Run without --offline (or with --fetch-policy cache) \
+                                     to use the site");
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("3").unwrap(), vec![3]);
+        assert_eq!(parse_days("1..4").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse_days("1..=4").unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(parse_days("5,1..=2,2").unwrap(), vec![1, 2, 5]);
+        assert_eq!(parse_days("20..=25").unwrap(), vec![20, 21, 22, 23, 24, 25]);
+        assert!(parse_days("4..4").is_err());
+        assert!(parse_days("1..=26").is_err());
+        assert!(parse_days("").is_err());
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// The source of the current time. Anything that depends on the time of day takes a Clock so that
+// it can be tested without waiting around.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+// A clock that only moves when something sleeps, so that tests don't wait around
+#[cfg(test)]
+pub struct FakeClock {
+    now: Mutex<SystemTime>,
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn at(now: SystemTime) -> FakeClock {
+        FakeClock { now: Mutex::new(now), sleeps: Mutex::new(Vec::new()) }
+    }
+
+    // Every sleep so far, in order
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+        self.sleeps.lock().unwrap().push(duration);
+    }
+}
+
+// Returns when the puzzle for the given day unlocks. Puzzles unlock at midnight US Eastern time,
+// which is always 05:00 UTC in December as daylight saving time is not in effect.
+pub fn unlock_time(year: u16, day: u8) -> SystemTime {
+    let days = days_since_epoch(year as i64, 12, day as i64);
+    UNIX_EPOCH + Duration::from_secs((days * 24 * 60 * 60 + 5 * 60 * 60) as u64)
+}
+
+// Returns how long until the puzzle for the given day unlocks (or None if it is already unlocked)
+pub fn time_until_unlock(clock: &dyn Clock, year: u16, day: u8) -> Option<Duration> {
+    unlock_time(year, day).duration_since(clock.now()).ok()
+        .filter(|duration| *duration > Duration::from_secs(0))
+}
+
+// Formats a duration to the second, e.g. '1d 2h 3m 4s'
+pub fn format_wait(duration: Duration) -> String {
+    let seconds = duration.as_secs() + if duration.subsec_nanos() > 0 { 1 } else { 0 };
+    let units = [(seconds / 86400, "d"), (seconds / 3600 % 24, "h"), (seconds / 60 % 60, "m")];
+    let mut parts = units.iter()
+        .skip_while(|(n, _)| *n == 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect::<Vec<_>>();
+    parts.push(format!("{}s", seconds % 60));
+    parts.join(" ")
+}
+
+// The number of days from 1970-01-01 to the given date in the (proleptic) Gregorian calendar. This
+// is Howard Hinnant's days_from_civil algorithm, which treats each year as starting in March so
+// that the leap day comes last.
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{days_since_epoch, format_wait, time_until_unlock, unlock_time, Clock};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+
+        fn sleep(&self, _duration: Duration) {}
+    }
+
+    #[test]
+    fn test_days_since_epoch() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(1969, 12, 31), -1);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11017);
+        assert_eq!(days_since_epoch(2020, 12, 1), 18597);
+        assert_eq!(days_since_epoch(2024, 2, 29) + 1, days_since_epoch(2024, 3, 1));
+    }
+
+    #[test]
+    fn test_unlock_time() {
+        // 2020-12-01T05:00:00Z
+        assert_eq!(unlock_time(2020, 1), UNIX_EPOCH + Duration::from_secs(1606798800));
+        // 2015-12-25T05:00:00Z
+        assert_eq!(unlock_time(2015, 25), UNIX_EPOCH + Duration::from_secs(1451019600));
+    }
+
+    #[test]
+    fn test_time_until_unlock() {
+        let clock = FixedClock(unlock_time(2020, 5) - Duration::from_secs(90));
+        assert_eq!(time_until_unlock(&clock, 2020, 5), Some(Duration::from_secs(90)));
+        assert_eq!(time_until_unlock(&clock, 2020, 6),
+                   Some(Duration::from_secs(90 + 24 * 60 * 60)));
+        assert_eq!(time_until_unlock(&clock, 2020, 4), None);
+        let clock = FixedClock(unlock_time(2020, 5));
+        assert_eq!(time_until_unlock(&clock, 2020, 5), None);
+    }
+
+    #[test]
+    fn test_format_wait() {
+        assert_eq!(format_wait(Duration::from_secs(0)), "0s");
+        assert_eq!(format_wait(Duration::from_millis(59_001)), "1m 0s");
+        assert_eq!(format_wait(Duration::from_secs(3600)), "1h 0m 0s");
+        assert_eq!(format_wait(Duration::from_secs(93784)), "1d 2h 3m 4s");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::clock;
+use crate::input_metadata::InputStatus;
+use crate::session_token::TokenSource;
+use reqwest::StatusCode;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Why talking to the site (or to the local store) failed. Each variant that wraps another error
+// says what was being done at the time in its context.
+#[derive(Debug)]
+pub enum FetchError {
+    // No session token was found in any of the places it is looked for
+    MissingToken { looked_in: Vec<TokenSource> },
+
+    // A session token was found, but it doesn't look like one. The token is masked so that it can
+    // be shown.
+    BadTokenFormat { token_source: TokenSource, masked_token: String },
+
+    // The site did not accept the session token (e.g. because it has expired)
+    Unauthorized { context: String, token_source: TokenSource },
+
+    // The puzzle hasn't unlocked yet, so there is no point in asking the site for it
+    NotYetUnlocked { year: u16, day: u8, remaining: Duration },
+
+    // The site answered with a status other than the one expected
+    HttpStatus { context: String, status: StatusCode },
+
+    // The site couldn't be reached (or the response couldn't be read)
+    Transport { context: String, source: reqwest::Error },
+
+    Io { context: String, source: io::Error },
+
+    // Something had to be downloaded, but the fetch policy doesn't allow it. The path is where it
+    // was expected in the local store (for things that are kept there).
+    Offline { what: String, path: Option<PathBuf> },
+
+    // The puzzle input in the local store is corrupt or doesn't look like puzzle input
+    CorruptLocalInput { year: u16, day: u8, path: PathBuf, status: InputStatus },
+
+    // None of the places puzzle input was looked for had the input for the day
+    NoInput { year: u16, day: u8, looked_in: String },
+
+    // The request doesn't make sense, e.g. there is no puzzle for the given day
+    InvalidRequest(String),
+
+    // The site answered with something we don't understand
+    UnexpectedResponse(String),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::MissingToken { looked_in } => {
+                let sources = looked_in.iter()
+                    .map(|source| source.to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "No session token was found. Looked in: {}", sources.join(", "))
+            },
+            FetchError::BadTokenFormat { token_source, masked_token } => {
+                write!(f, "Session token from {} is not in the right format. \
+                           Expected 96 lowercase hex digits. Got: {}", token_source, masked_token)
+            },
+            FetchError::Unauthorized { context, token_source } => {
+                write!(f, "{}: The site did not accept the session token from {}",
+                       context, token_source)
+            },
+            FetchError::NotYetUnlocked { year, day, remaining } => {
+                write!(f, "The puzzle for {} day {} unlocks in {}",
+                       year, day, clock::format_wait(*remaining))
+            },
+            FetchError::HttpStatus { context, status } => {
+                write!(f, "{}: Got status code = {}", context, status)
+            },
+            FetchError::Transport { context, source } => write!(f, "{}: {}", context, source),
+            FetchError::Io { context, source } => write!(f, "{}: {}", context, source),
+            FetchError::Offline { what, path: Some(path) } => {
+                write!(f, "{} is not in the local store (expected at {}) and fetching is turned \
+                           off", what, path.display())
+            },
+            FetchError::Offline { what, path: None } => {
+                write!(f, "{} needs the site, but fetching is turned off", what)
+            },
+            FetchError::CorruptLocalInput { year, day, path, status } => {
+                write!(f, "The puzzle input for {} day {} in the local store ({}) is {}",
+                       year, day, path.display(), status)
+            },
+            FetchError::NoInput { year, day, looked_in } => {
+                write!(f, "No puzzle input for {} day {} was found in {}", year, day, looked_in)
+            },
+            FetchError::InvalidRequest(message) => write!(f, "{}", message),
+            FetchError::UnexpectedResponse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for FetchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FetchError::Transport { source, .. } => Some(source),
+            FetchError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// What was recorded about a puzzle input when it was downloaded. It is stored next to the input
+// (e.g. 'puzzle/2020/1.meta' for 'puzzle/2020/1') so that the input can be checked later on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMetadata {
+    pub fetched_at: SystemTime,
+    pub source_url: String,
+
+    // The length of the input in bytes
+    pub length: usize,
+
+    // The SHA-256 of the input as lowercase hex digits
+    pub sha256: String,
+
+    // The name of the account the input was downloaded for (if it is known)
+    pub account: Option<String>,
+}
+
+impl InputMetadata {
+    // Describes the given input as downloaded from the given URL at the given time
+    pub fn describe(input: &str, source_url: &str, fetched_at: SystemTime, account: Option<&str>)
+        -> InputMetadata {
+        InputMetadata {
+            fetched_at,
+            source_url: source_url.to_string(),
+            length: input.len(),
+            sha256: sha256(input),
+            account: account.map(|account| account.to_string()),
+        }
+    }
+
+    // Parses metadata in the format written by to_file_contents: one 'key value' pair per line
+    pub fn parse(text: &str) -> Result<InputMetadata, String> {
+        let mut fetched_at = None;
+        let mut source_url = None;
+        let mut length = None;
+        let mut sha256 = None;
+        let mut account = None;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let mut fields = line.splitn(2, ' ');
+            let key = fields.next().unwrap();
+            let value = fields.next().unwrap_or("").trim();
+            match key {
+                "fetched_at" => fetched_at = value.parse::<u64>().ok()
+                    .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds)),
+                "source_url" => source_url = Some(value.to_string()),
+                "length" => length = value.parse::<usize>().ok(),
+                "sha256" => sha256 = Some(value.to_string()),
+                "account" => account = Some(value.to_string()),
+                _ => {},
+            }
+        }
+        match (fetched_at, source_url, length, sha256) {
+            (Some(fetched_at), Some(source_url), Some(length), Some(sha256)) => {
+                Ok(InputMetadata { fetched_at, source_url, length, sha256, account })
+            },
+            _ => Err("The metadata is incomplete".to_string()),
+        }
+    }
+
+    pub fn to_file_contents(&self) -> String {
+        let fetched_at = self.fetched_at.duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let mut contents = format!("fetched_at {}\nsource_url {}\nlength {}\nsha256 {}\n",
+                                   fetched_at, self.source_url, self.length, self.sha256);
+        if let Some(account) = self.account.as_ref() {
+            contents.push_str(&format!("account {}\n", account));
+        }
+        contents
+    }
+}
+
+// What we can tell about a stored puzzle input
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputStatus {
+    // There is no stored input
+    Missing,
+
+    // The input matches the metadata recorded when it was downloaded
+    Valid,
+
+    // There is no metadata to check the input against (e.g. it was saved by hand), but nothing
+    // looks wrong with it
+    Unverified,
+
+    // The input doesn't match the metadata recorded when it was downloaded
+    Corrupt(String),
+
+    // The input doesn't look like puzzle input at all (e.g. it is an error page)
+    Suspicious(String),
+}
+
+impl InputStatus {
+    // Checks an input against its metadata (if any)
+    pub fn check(input: &str, metadata: Option<&InputMetadata>) -> InputStatus {
+        if let Some(metadata) = metadata {
+            if input.len() != metadata.length {
+                return InputStatus::Corrupt(format!("expected {} bytes but found {}",
+                                                    metadata.length, input.len()));
+            }
+            if sha256(input) != metadata.sha256 {
+                return InputStatus::Corrupt("the SHA-256 does not match".to_string());
+            }
+        }
+        if let Some(reason) = suspicious_content(input) {
+            return InputStatus::Suspicious(reason.to_string());
+        }
+        match metadata {
+            Some(_) => InputStatus::Valid,
+            None => InputStatus::Unverified,
+        }
+    }
+
+    // Whether the input should be replaced by downloading it again
+    pub fn is_bad(&self) -> bool {
+        matches!(self, InputStatus::Corrupt(_) | InputStatus::Suspicious(_))
+    }
+}
+
+impl fmt::Display for InputStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputStatus::Missing => write!(f, "missing"),
+            InputStatus::Valid => write!(f, "ok"),
+            InputStatus::Unverified => write!(f, "ok (no metadata to check against)"),
+            InputStatus::Corrupt(reason) => write!(f, "corrupt: {}", reason),
+            InputStatus::Suspicious(reason) => write!(f, "suspicious: {}", reason),
+        }
+    }
+}
+
+// Returns the SHA-256 of the given text as lowercase hex digits
+pub fn sha256(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Returns why the given text doesn't look like puzzle input (if it doesn't). Puzzle inputs are
+// never empty, and the site sends back HTML or a short message when it refuses to give one out.
+fn suspicious_content(text: &str) -> Option<&'static str> {
+    let start = text.trim_start().chars().take(16).collect::<String>().to_lowercase();
+    if text.trim().is_empty() {
+        Some("the input is empty")
+    } else if start.starts_with("<!doctype") || start.starts_with("<html") {
+        Some("the input is an HTML page")
+    } else if text.starts_with("Puzzle inputs differ by user")
+        || text.starts_with("Please don't repeatedly request this endpoint") {
+        Some("the input is an error message from the site")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_metadata::{sha256, InputMetadata, InputStatus};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(sha256(""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256("abc"),
+                   "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn test_file_contents_round_trip() {
+        let fetched_at = UNIX_EPOCH + Duration::from_secs(1606798801);
+        let url = "https://adventofcode.com/2020/day/1/input";
+        let metadata = InputMetadata::describe("abc\n", url, fetched_at, Some("akaritakai"));
+        assert_eq!(metadata.to_file_contents(), format!("fetched_at 1606798801\n\
+                                                         source_url {}\n\
+                                                         length 4\n\
+                                                         sha256 {}\n\
+                                                         account akaritakai\n",
+                                                        url, sha256("abc\n")));
+        assert_eq!(InputMetadata::parse(&metadata.to_file_contents()).unwrap(), metadata);
+        let metadata = InputMetadata::describe("abc\n", url, fetched_at, None);
+        assert!(!metadata.to_file_contents().contains("account"));
+        assert_eq!(InputMetadata::parse(&metadata.to_file_contents()).unwrap(), metadata);
+        assert!(InputMetadata::parse("fetched_at 1606798801\nlength 4\n").is_err());
+        assert!(InputMetadata::parse("").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let url = "https://adventofcode.com/2020/day/1/input";
+        let metadata = InputMetadata::describe("1\n2\n3\n", url, UNIX_EPOCH, None);
+        assert_eq!(InputStatus::check("1\n2\n3\n", Some(&metadata)), InputStatus::Valid);
+        assert_eq!(InputStatus::check("1\n2\n3\n", None), InputStatus::Unverified);
+        assert!(InputStatus::check("1\n2\n", Some(&metadata)).is_bad());
+        assert!(InputStatus::check("1\n2\n4\n", Some(&metadata)).is_bad());
+        let suspicious = vec![
+            "",
+            " \n",
+            "<!DOCTYPE html>\n<html lang=\"en-us\"></html>",
+            "<html><body>502 Bad Gateway</body></html>",
+            "Puzzle inputs differ by user.  Please log in to get your puzzle input.\n",
+        ];
+        for input in suspicious {
+            assert!(matches!(InputStatus::check(input, None), InputStatus::Suspicious(_)));
+        }
+        assert!(!InputStatus::Unverified.is_bad());
+        assert!(!InputStatus::Missing.is_bad());
+    }
+}
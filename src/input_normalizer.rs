@@ -0,0 +1,182 @@
+use std::fmt;
+
+// A change made to puzzle input to bring it into the form the solvers expect: Unix line endings, no
+// byte order mark and exactly one newline at the end (and, in strict mode, no trailing whitespace)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputChange {
+    ByteOrderMark,
+
+    // The number of CRLF line endings that were converted to LF
+    LineEndings(usize),
+
+    // The number of lines that had trailing whitespace removed
+    TrailingWhitespace(usize),
+
+    // The number of blank lines that were removed from the end
+    TrailingBlankLines(usize),
+
+    MissingFinalNewline,
+}
+
+impl fmt::Display for InputChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputChange::ByteOrderMark => write!(f, "removed a byte order mark"),
+            InputChange::LineEndings(count) => {
+                write!(f, "converted {} CRLF line ending(s) to LF", count)
+            },
+            InputChange::TrailingWhitespace(count) => {
+                write!(f, "removed trailing whitespace from {} line(s)", count)
+            },
+            InputChange::TrailingBlankLines(count) => {
+                write!(f, "removed {} blank line(s) at the end", count)
+            },
+            InputChange::MissingFinalNewline => write!(f, "added a newline at the end"),
+        }
+    }
+}
+
+// Puzzle input in the form the solvers expect, along with what had to be changed to get it there
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedInput {
+    pub input: String,
+    pub changes: Vec<InputChange>,
+}
+
+impl NormalizedInput {
+    // Describes the changes for the input of the given day (or None if nothing was changed)
+    pub fn describe_changes(&self, year: u16, day: u8) -> Option<String> {
+        if self.changes.is_empty() {
+            return None;
+        }
+        let changes = self.changes.iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        Some(format!("Puzzle input for {} day {} was normalized: {}",
+                     year, day, changes.join(", ")))
+    }
+}
+
+// Normalizes puzzle input that may have been saved on another platform or edited by hand: removes
+// a byte order mark, converts CRLF line endings to LF and leaves exactly one newline at the end.
+// Whitespace within lines is left alone, as it can matter to a puzzle. Input that is already
+// normalized is returned as it is, so this can safely be applied more than once. Empty input stays
+// empty.
+pub fn normalize(input: &str) -> NormalizedInput {
+    normalize_with(input, false)
+}
+
+// Same as normalize, but also removes trailing whitespace from every line
+pub fn normalize_strict(input: &str) -> NormalizedInput {
+    normalize_with(input, true)
+}
+
+fn normalize_with(input: &str, trim_lines: bool) -> NormalizedInput {
+    let mut changes = Vec::new();
+    let input = match input.strip_prefix('\u{feff}') {
+        Some(rest) => {
+            changes.push(InputChange::ByteOrderMark);
+            rest
+        },
+        None => input,
+    };
+    let crlf_count = input.matches("\r\n").count();
+    if crlf_count > 0 {
+        changes.push(InputChange::LineEndings(crlf_count));
+    }
+    // lines() takes care of both kinds of line ending
+    let mut lines = Vec::new();
+    let mut trimmed_count = 0;
+    for line in input.lines() {
+        let trimmed = if trim_lines { line.trim_end() } else { line };
+        if trimmed.len() != line.len() {
+            trimmed_count += 1;
+        }
+        lines.push(trimmed);
+    }
+    if trimmed_count > 0 {
+        changes.push(InputChange::TrailingWhitespace(trimmed_count));
+    }
+    let blank_count = lines.iter().rev().take_while(|line| line.is_empty()).count();
+    lines.truncate(lines.len() - blank_count);
+    if blank_count > 0 {
+        changes.push(InputChange::TrailingBlankLines(blank_count));
+    }
+    if lines.is_empty() {
+        return NormalizedInput { input: String::new(), changes };
+    }
+    if !input.ends_with('\n') && blank_count == 0 {
+        changes.push(InputChange::MissingFinalNewline);
+    }
+    NormalizedInput { input: lines.join("\n") + "\n", changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input_normalizer::{normalize, normalize_strict, InputChange};
+
+    #[test]
+    fn test_normalized_input_is_unchanged() {
+        for input in ["1\n2\n", "a b\n\nc\n", ""].iter() {
+            let normalized = normalize(input);
+            assert_eq!(normalized.input, *input);
+            assert!(normalized.changes.is_empty());
+            assert_eq!(normalized.describe_changes(2020, 1), None);
+        }
+    }
+
+    #[test]
+    fn test_crlf() {
+        let normalized = normalize("a b\r\n\r\nc\r\n");
+        assert_eq!(normalized.input, "a b\n\nc\n");
+        assert_eq!(normalized.changes, vec![InputChange::LineEndings(3)]);
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let normalized = normalize("\u{feff}1\n2\n");
+        assert_eq!(normalized.input, "1\n2\n");
+        assert_eq!(normalized.changes, vec![InputChange::ByteOrderMark]);
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        // Only removed in strict mode
+        let normalized = normalize("a b  \n \t\nc\t\n");
+        assert_eq!(normalized.input, "a b  \n \t\nc\t\n");
+        assert!(normalized.changes.is_empty());
+        let normalized = normalize_strict("a b  \n \t\nc\t\n");
+        assert_eq!(normalized.input, "a b\n\nc\n");
+        assert_eq!(normalized.changes, vec![InputChange::TrailingWhitespace(3)]);
+    }
+
+    #[test]
+    fn test_end_of_input() {
+        let normalized = normalize("1\n2");
+        assert_eq!(normalized.input, "1\n2\n");
+        assert_eq!(normalized.changes, vec![InputChange::MissingFinalNewline]);
+        let normalized = normalize("1\n2\n\n\n");
+        assert_eq!(normalized.input, "1\n2\n");
+        assert_eq!(normalized.changes, vec![InputChange::TrailingBlankLines(2)]);
+        let normalized = normalize("\n \n");
+        assert_eq!(normalized.input, "\n \n");
+        assert!(normalized.changes.is_empty());
+        let normalized = normalize_strict("\n \n");
+        assert_eq!(normalized.input, "");
+        assert_eq!(normalized.changes, vec![InputChange::TrailingWhitespace(1),
+                                            InputChange::TrailingBlankLines(2)]);
+    }
+
+    #[test]
+    fn test_everything_at_once() {
+        let normalized = normalize("\u{feff}1 \r\n2\r\n\r\n");
+        assert_eq!(normalized.input, "1 \n2\n");
+        let normalized = normalize_strict("\u{feff}1 \r\n2\r\n\r\n");
+        assert_eq!(normalized.input, "1\n2\n");
+        assert_eq!(normalized.describe_changes(2020, 1).unwrap(),
+                   "Puzzle input for 2020 day 1 was normalized: removed a byte order mark, \
+                    converted 3 CRLF line ending(s) to LF, removed trailing whitespace from 1 \
+                    line(s), removed 1 blank line(s) at the end");
+        assert_eq!(normalize_strict(&normalized.input).changes, vec![]);
+    }
+}
@@ -0,0 +1,688 @@
+use crate::fetch_error::FetchError;
+use crate::input_normalizer::{self, NormalizedInput};
+use crate::puzzle_input_fetcher::{InputOrigin, PuzzleInputFetcher};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Somewhere puzzle input can be found. Sources are tried in order in an InputChain, so a source
+// that doesn't have the input for a day says so rather than failing: an error means the source
+// should have had the input but couldn't give it out.
+pub trait InputSource: Send + Sync {
+    // Describes the source in messages, e.g. "the directory 'puzzle'"
+    fn describe(&self) -> String;
+
+    // Returns the puzzle input for the given year and day along with where it was found, or None
+    // if this source doesn't have it
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>>;
+
+    // Sleeps until this source could have the input for the given day. Most sources have what
+    // they have straight away, so by default this doesn't wait at all.
+    fn wait_for_input(&self, _year: u16, _day: u8) {}
+
+    // Same as find_input, but fails if this source doesn't have the input. The input is normalized
+    // (see input_normalizer::normalize) so that it is in the form the solvers expect, whichever
+    // source it came from.
+    fn get_input(&self, year: u16, day: u8) -> Result<(String, InputOrigin)> {
+        self.get_normalized_input(year, day, false)
+            .map(|(normalized, origin)| (normalized.input, origin))
+    }
+
+    // Same as get_input, but also says what had to be changed to normalize the input. Strict
+    // normalization also removes trailing whitespace from every line.
+    fn get_normalized_input(&self, year: u16, day: u8, strict: bool)
+        -> Result<(NormalizedInput, InputOrigin)> {
+        let (input, origin) = self.find_input(year, day)?
+            .ok_or_else(|| FetchError::NoInput { year, day, looked_in: self.describe() })?;
+        let normalized = if strict {
+            input_normalizer::normalize_strict(&input)
+        } else {
+            input_normalizer::normalize(&input)
+        };
+        Ok((normalized, origin))
+    }
+}
+
+// Tries each of its sources in order, using the first one that has the input
+pub struct InputChain {
+    sources: Vec<Box<dyn InputSource>>,
+}
+
+impl InputChain {
+    pub fn new(sources: Vec<Box<dyn InputSource>>) -> InputChain {
+        InputChain { sources }
+    }
+}
+
+impl InputSource for InputChain {
+    fn describe(&self) -> String {
+        match self.sources.len() {
+            0 => "no sources".to_string(),
+            _ => self.sources.iter()
+                .map(|source| source.describe())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        for source in &self.sources {
+            if let Some(found) = source.find_input(year, day)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    fn wait_for_input(&self, year: u16, day: u8) {
+        for source in &self.sources {
+            source.wait_for_input(year, day);
+        }
+    }
+}
+
+// The usual place to get puzzle input from: the fetcher's local store, or else the site (keeping
+// what is downloaded in the local store for next time). Both sources go through the fetcher's
+// in-memory cache, so each day is only loaded once even when several threads ask for it at once.
+pub fn default_chain(fetcher: &Arc<PuzzleInputFetcher>) -> InputChain {
+    InputChain::new(vec![
+        Box::new(StoreSource::new(fetcher.clone())),
+        Box::new(SiteSource::new(fetcher.clone()).with_store(true)),
+    ])
+}
+
+// Puzzle input downloaded from the site. As the site has every puzzle input (once it has
+// unlocked), this source never lacks input: it fails instead. Nothing is kept in the local store
+// unless asked for, so the site can be used without touching it.
+pub struct SiteSource {
+    fetcher: Arc<PuzzleInputFetcher>,
+
+    // Whether to keep what is downloaded in the fetcher's local store
+    store: bool,
+}
+
+impl SiteSource {
+    pub fn new(fetcher: Arc<PuzzleInputFetcher>) -> SiteSource {
+        SiteSource { fetcher, store: false }
+    }
+
+    // Changes whether what is downloaded is kept in the fetcher's local store (along with its
+    // metadata)
+    pub fn with_store(mut self, store: bool) -> SiteSource {
+        self.store = store;
+        self
+    }
+}
+
+impl InputSource for SiteSource {
+    fn describe(&self) -> String {
+        format!("the site at {}", self.fetcher.base_url())
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        self.fetcher.get_cached_puzzle_input(year, day, || {
+            let input = self.fetcher.download_puzzle_input(year, day)?;
+            if self.store {
+                self.fetcher.store_puzzle_input(year, day, &input);
+            }
+            Ok(Some((input, InputOrigin::Remote)))
+        })
+    }
+
+    fn wait_for_input(&self, year: u16, day: u8) {
+        self.fetcher.wait_for_unlock(year, day);
+    }
+}
+
+// Puzzle input in the fetcher's local store. Unlike a DirectorySource, stored input is checked
+// against its metadata, and input that can't be trusted is left for a later source (such as the
+// site) to replace.
+pub struct StoreSource {
+    fetcher: Arc<PuzzleInputFetcher>,
+}
+
+impl StoreSource {
+    pub fn new(fetcher: Arc<PuzzleInputFetcher>) -> StoreSource {
+        StoreSource { fetcher }
+    }
+}
+
+impl InputSource for StoreSource {
+    fn describe(&self) -> String {
+        format!("the local store '{}'", self.fetcher.input_path().display())
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        self.fetcher.get_cached_puzzle_input(year, day, || {
+            let input = self.fetcher.find_stored_puzzle_input(year, day)?;
+            Ok(input.map(|input| (input, InputOrigin::Local)))
+        })
+    }
+}
+
+// Puzzle input stored in a directory in the same layout as the local store, e.g. 'dir/2020/1' for
+// the input of day 1 in 2020. Unlike the local store, the input is used as it is without being
+// checked against any metadata.
+pub struct DirectorySource {
+    path: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(path: &Path) -> DirectorySource {
+        DirectorySource { path: path.to_path_buf() }
+    }
+}
+
+impl InputSource for DirectorySource {
+    fn describe(&self) -> String {
+        format!("the directory '{}'", self.path.display())
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        let path = self.path.join(year.to_string()).join(day.to_string());
+        match fs::read_to_string(&path) {
+            Ok(input) => Ok(Some((input, InputOrigin::Local))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(FetchError::Io {
+                context: format!("Failed to read puzzle input from {}", path.display()),
+                source: e,
+            }),
+        }
+    }
+}
+
+// Puzzle input bundled into a single tar archive in the same layout as the local store, e.g. made
+// with 'tar cf inputs.tar -C puzzle 2020'. Entries whose names don't end in '<year>/<day>' (such as
+// the metadata kept next to the input) are ignored. The whole archive is read when it is opened.
+pub struct ArchiveSource {
+    path: PathBuf,
+    inputs: HashMap<(u16, u8), String>,
+}
+
+impl ArchiveSource {
+    pub fn open(path: &Path) -> Result<ArchiveSource> {
+        let io_error = |e| FetchError::Io {
+            context: format!("Failed to read puzzle input archive {}", path.display()),
+            source: e,
+        };
+        let file = fs::File::open(path).map_err(io_error)?;
+        ArchiveSource::from_reader(path, file).map_err(io_error)
+    }
+
+    fn from_reader<R: Read>(path: &Path, reader: R) -> io::Result<ArchiveSource> {
+        let mut archive = tar::Archive::new(reader);
+        let mut inputs = HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if let Some(key) = archive_key(&entry.path()?) {
+                let mut input = String::new();
+                entry.read_to_string(&mut input)?;
+                inputs.insert(key, input);
+            }
+        }
+        Ok(ArchiveSource { path: path.to_path_buf(), inputs })
+    }
+}
+
+impl InputSource for ArchiveSource {
+    fn describe(&self) -> String {
+        format!("the archive '{}'", self.path.display())
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        Ok(self.inputs.get(&(year, day)).map(|input| (input.clone(), InputOrigin::Local)))
+    }
+}
+
+// Reads the year and day from the last two components of a path in an archive, e.g. 2020 and 1
+// from 'puzzle/2020/1'
+fn archive_key(path: &Path) -> Option<(u16, u8)> {
+    let day = path.file_name()?.to_str()?.parse::<u8>().ok()?;
+    let year = path.parent()?.file_name()?.to_str()?.parse::<u16>().ok()?;
+    Some((year, day))
+}
+
+// The puzzle input for a single day read from a file given by the user. As the file was asked for,
+// it is an error for it to be missing.
+pub struct FileSource {
+    year: u16,
+    day: u8,
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(year: u16, day: u8, path: &Path) -> FileSource {
+        FileSource { year, day, path: path.to_path_buf() }
+    }
+}
+
+impl InputSource for FileSource {
+    fn describe(&self) -> String {
+        format!("the file '{}' (for {} day {})", self.path.display(), self.year, self.day)
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        if (year, day) != (self.year, self.day) {
+            return Ok(None);
+        }
+        fs::read_to_string(&self.path)
+            .map(|input| Some((input, InputOrigin::File)))
+            .map_err(|e| FetchError::Io {
+                context: format!("Failed to read puzzle input from {}", self.path.display()),
+                source: e,
+            })
+    }
+}
+
+// The puzzle input for a single day read from standard input (or any other reader). The reader is
+// only read the first time the input is asked for, and what came of it (the input or the error) is
+// kept for any later calls.
+pub struct StdinSource {
+    year: u16,
+    day: u8,
+    reader: Mutex<Option<Box<dyn Read + Send>>>,
+    input: Mutex<Option<io::Result<String>>>,
+}
+
+impl StdinSource {
+    pub fn new(year: u16, day: u8) -> StdinSource {
+        StdinSource::from_reader(year, day, Box::new(io::stdin()))
+    }
+
+    pub fn from_reader(year: u16, day: u8, reader: Box<dyn Read + Send>) -> StdinSource {
+        StdinSource {
+            year,
+            day,
+            reader: Mutex::new(Some(reader)),
+            input: Mutex::new(None),
+        }
+    }
+}
+
+impl InputSource for StdinSource {
+    fn describe(&self) -> String {
+        format!("standard input (for {} day {})", self.year, self.day)
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        if (year, day) != (self.year, self.day) {
+            return Ok(None);
+        }
+        let mut cached_input = self.input.lock().unwrap();
+        if cached_input.is_none() {
+            let mut input = String::new();
+            let result = match self.reader.lock().unwrap().take() {
+                Some(mut reader) => reader.read_to_string(&mut input).map(|_| input),
+                None => Ok(input),
+            };
+            *cached_input = Some(result);
+        }
+        match cached_input.as_ref().unwrap() {
+            Ok(input) => Ok(Some((input.clone(), InputOrigin::Stdin))),
+            // io::Error can't be cloned, so a copy of it is reported each time
+            Err(e) => Err(FetchError::Io {
+                context: "Failed to read puzzle input from standard input".to_string(),
+                source: io::Error::new(e.kind(), e.to_string()),
+            }),
+        }
+    }
+}
+
+// Puzzle input held in memory, e.g. for tests or for input that is built into a program
+#[derive(Default)]
+pub struct MemorySource {
+    inputs: HashMap<(u16, u8), String>,
+}
+
+impl MemorySource {
+    pub fn new() -> MemorySource {
+        MemorySource::default()
+    }
+
+    pub fn with_input(mut self, year: u16, day: u8, input: &str) -> MemorySource {
+        self.inputs.insert((year, day), input.to_string());
+        self
+    }
+}
+
+impl InputSource for MemorySource {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn find_input(&self, year: u16, day: u8) -> Result<Option<(String, InputOrigin)>> {
+        Ok(self.inputs.get(&(year, day)).map(|input| (input.clone(), InputOrigin::Memory)))
+    }
+}
+
+type Result<T> = std::result::Result<T, FetchError>;
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::FakeClock;
+    use crate::fetch_error::FetchError;
+    use crate::input_normalizer::InputChange;
+    use crate::input_source::{archive_key, default_chain, ArchiveSource, DirectorySource,
+                              FileSource, InputChain, InputSource, MemorySource, SiteSource,
+                              StdinSource, StoreSource};
+    use crate::puzzle_input_fetcher::{FetchPolicy, InputOrigin, PuzzleInputFetcher};
+    use std::fs;
+    use std::io::{self, Read};
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::SystemTime;
+    use httpmock::Method::GET;
+    use httpmock::MockServer;
+    use tempfile::{tempdir, NamedTempFile};
+
+    // Starts a site that has the given input for 2020 day 1 and accepts any session token
+    fn start_site(input: &str) -> MockServer {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/settings");
+            then.status(200)
+                .body("<header><div class=\"user\">akaritakai</div></header>");
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/2020/day/1/input");
+            then.status(200)
+                .body(input);
+        });
+        server
+    }
+
+    fn create_session_token_file() -> NamedTempFile {
+        let session_token_path = NamedTempFile::new().unwrap();
+        fs::write(session_token_path.path(), "0123456789abcdef".repeat(6)).unwrap();
+        session_token_path
+    }
+
+    // Creates a fetcher whose clock doesn't sleep for real, as in the fetcher's own tests
+    fn create_fetcher(base_url: &str, input_path: &Path, session_token_path: &Path)
+        -> PuzzleInputFetcher {
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        PuzzleInputFetcher::create_with_clock(base_url, input_path, session_token_path, clock)
+    }
+
+    #[test]
+    fn test_memory_source() {
+        let source = MemorySource::new().with_input(2020, 1, "1\n2\n");
+        assert_eq!(source.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Memory)));
+        assert_eq!(source.find_input(2020, 2).unwrap(), None);
+        let error = source.get_input(2019, 1).unwrap_err();
+        assert_eq!(error.to_string(), "No puzzle input for 2019 day 1 was found in memory");
+    }
+
+    #[test]
+    fn test_directory_source() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("2020")).unwrap();
+        fs::write(dir.path().join("2020/1"), "1\n2\n").unwrap();
+        fs::create_dir(dir.path().join("2020/2")).unwrap();
+        let source = DirectorySource::new(dir.path());
+        assert_eq!(source.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Local)));
+        assert_eq!(source.find_input(2020, 3).unwrap(), None);
+        assert_eq!(source.find_input(2019, 1).unwrap(), None);
+        // Something is in the way, so it is an error rather than missing input
+        assert!(matches!(source.find_input(2020, 2), Err(FetchError::Io { .. })));
+    }
+
+    #[test]
+    fn test_archive_source() {
+        let dir = tempdir().unwrap();
+        let mut builder = tar::Builder::new(Vec::new());
+        let entries = [("puzzle/2020/1", "1\n2\n"), ("2019/25", "25\n"),
+                       ("puzzle/2020/1.meta", "length 4\n"), ("README", "Inputs\n")];
+        for (path, contents) in entries.iter() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        let archive_path = dir.path().join("inputs.tar");
+        fs::write(&archive_path, builder.into_inner().unwrap()).unwrap();
+        let source = ArchiveSource::open(&archive_path).unwrap();
+        assert_eq!(source.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Local)));
+        assert_eq!(source.find_input(2019, 25).unwrap(),
+                   Some(("25\n".to_string(), InputOrigin::Local)));
+        assert_eq!(source.find_input(2020, 2).unwrap(), None);
+        assert_eq!(source.inputs.len(), 2);
+        assert!(ArchiveSource::open(&dir.path().join("missing.tar")).is_err());
+    }
+
+    #[test]
+    fn test_archive_key() {
+        assert_eq!(archive_key(Path::new("2020/1")), Some((2020, 1)));
+        assert_eq!(archive_key(Path::new("./puzzle/2020/25")), Some((2020, 25)));
+        assert_eq!(archive_key(Path::new("2020/1.meta")), None);
+        assert_eq!(archive_key(Path::new("1")), None);
+    }
+
+    #[test]
+    fn test_file_source() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("edge-case.txt");
+        fs::write(&path, "1\n2\n").unwrap();
+        let source = FileSource::new(2020, 4, &path);
+        assert_eq!(source.find_input(2020, 4).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::File)));
+        assert_eq!(source.find_input(2020, 5).unwrap(), None);
+        let source = FileSource::new(2020, 4, &dir.path().join("missing.txt"));
+        assert!(matches!(source.find_input(2020, 4), Err(FetchError::Io { .. })));
+    }
+
+    // Counts how many times it is read from
+    struct CountingReader {
+        input: io::Cursor<Vec<u8>>,
+        reads: Arc<Mutex<usize>>,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            *self.reads.lock().unwrap() += 1;
+            self.input.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_stdin_source() {
+        let reads = Arc::new(Mutex::new(0));
+        let reader = CountingReader {
+            input: io::Cursor::new(b"1\n2\n".to_vec()),
+            reads: reads.clone(),
+        };
+        let source = StdinSource::from_reader(2020, 4, Box::new(reader));
+        assert_eq!(source.find_input(2020, 1).unwrap(), None);
+        assert_eq!(*reads.lock().unwrap(), 0);
+        assert_eq!(source.find_input(2020, 4).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Stdin)));
+        let reads_so_far = *reads.lock().unwrap();
+        assert_eq!(source.get_input(2020, 4).unwrap().0, "1\n2\n");
+        assert_eq!(*reads.lock().unwrap(), reads_so_far);
+    }
+
+    // Fails every read
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"))
+        }
+    }
+
+    #[test]
+    fn test_stdin_source_keeps_reporting_read_error() {
+        let source = StdinSource::from_reader(2020, 4, Box::new(FailingReader));
+        for _ in 0..2 {
+            match source.find_input(2020, 4) {
+                Err(FetchError::Io { source, .. }) => {
+                    assert_eq!(source.to_string(), "broken pipe")
+                },
+                result => panic!("Expected a read error, got {:?}", result),
+            }
+        }
+        assert!(source.get_input(2020, 4).is_err());
+    }
+
+    #[test]
+    fn test_get_input_is_normalized() {
+        let source = MemorySource::new().with_input(2020, 1, "\u{feff}1 \r\n2\r\n\r\n");
+        assert_eq!(source.find_input(2020, 1).unwrap().unwrap().0, "\u{feff}1 \r\n2\r\n\r\n");
+        assert_eq!(source.get_input(2020, 1).unwrap().0, "1 \n2\n");
+        let (normalized, origin) = source.get_normalized_input(2020, 1, false).unwrap();
+        assert_eq!(normalized.input, "1 \n2\n");
+        assert_eq!(normalized.changes, vec![InputChange::ByteOrderMark,
+                                            InputChange::LineEndings(3),
+                                            InputChange::TrailingBlankLines(1)]);
+        assert_eq!(origin, InputOrigin::Memory);
+        let (normalized, _) = source.get_normalized_input(2020, 1, true).unwrap();
+        assert_eq!(normalized.input, "1\n2\n");
+        assert!(normalized.changes.contains(&InputChange::TrailingWhitespace(1)));
+    }
+
+    #[test]
+    fn test_chain_uses_first_source_with_input() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("2020")).unwrap();
+        fs::write(dir.path().join("2020/1"), "from directory\n").unwrap();
+        fs::write(dir.path().join("2020/2"), "from directory\n").unwrap();
+        let chain = InputChain::new(vec![
+            Box::new(MemorySource::new().with_input(2020, 1, "from memory\n")),
+            Box::new(DirectorySource::new(dir.path())),
+        ]);
+        assert_eq!(chain.get_input(2020, 1).unwrap(),
+                   ("from memory\n".to_string(), InputOrigin::Memory));
+        assert_eq!(chain.get_input(2020, 2).unwrap(),
+                   ("from directory\n".to_string(), InputOrigin::Local));
+        let error = chain.get_input(2020, 3).unwrap_err();
+        assert_eq!(error.to_string(),
+                   format!("No puzzle input for 2020 day 3 was found in memory, the directory \
+                            '{}'", dir.path().display()));
+        assert!(InputChain::new(Vec::new()).get_input(2020, 1).is_err());
+    }
+
+    #[test]
+    fn test_chain_stops_at_errors() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("2020/1")).unwrap();
+        let chain = InputChain::new(vec![
+            Box::new(DirectorySource::new(dir.path())),
+            Box::new(MemorySource::new().with_input(2020, 1, "from memory")),
+        ]);
+        assert!(matches!(chain.get_input(2020, 1), Err(FetchError::Io { .. })));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_site_source() {
+        let server = start_site("1\n2\n");
+        let dir = tempdir().unwrap();
+        let session_token_path = create_session_token_file();
+        let create = || Arc::new(create_fetcher(&server.base_url(), dir.path(),
+                                                session_token_path.path()));
+        let source = SiteSource::new(create());
+        assert_eq!(source.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Remote)));
+        assert_eq!(source.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Memory)));
+        // Nothing is stored unless asked for
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+        let source = SiteSource::new(create()).with_store(true);
+        assert_eq!(source.get_input(2020, 1).unwrap().0, "1\n2\n");
+        assert_eq!(fs::read_to_string(dir.path().join("2020/1")).unwrap(), "1\n2\n");
+        assert!(dir.path().join("2020/1.meta").exists());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_default_chain() {
+        let server = start_site("1\n2\n");
+        let dir = tempdir().unwrap();
+        let session_token_path = create_session_token_file();
+        // Each fetcher has its own in-memory cache, so a new one is made to see the local store
+        let create = || Arc::new(create_fetcher(&server.base_url(), dir.path(),
+                                                session_token_path.path()));
+        let fetcher = create();
+        let chain = default_chain(&fetcher);
+        assert_eq!(chain.get_input(2020, 1).unwrap(),
+                   ("1\n2\n".to_string(), InputOrigin::Remote));
+        assert_eq!(chain.get_input(2020, 1).unwrap(),
+                   ("1\n2\n".to_string(), InputOrigin::Memory));
+        // What was downloaded is now in the local store, and is checked against its metadata
+        let store = StoreSource::new(create());
+        assert_eq!(store.find_input(2020, 1).unwrap(),
+                   Some(("1\n2\n".to_string(), InputOrigin::Local)));
+        fs::write(dir.path().join("2020/1"), "changed\n").unwrap();
+        let fetcher = create();
+        assert_eq!(StoreSource::new(fetcher.clone()).find_input(2020, 1).unwrap(), None);
+        assert_eq!(default_chain(&fetcher).get_input(2020, 1).unwrap(),
+                   ("1\n2\n".to_string(), InputOrigin::Remote));
+        // Offline, the site is never asked
+        let fetcher = create_fetcher(&server.base_url(), dir.path(), session_token_path.path())
+            .with_fetch_policy(FetchPolicy::OfflineOnly);
+        let chain = default_chain(&Arc::new(fetcher));
+        assert_eq!(chain.get_input(2020, 1).unwrap().0, "1\n2\n");
+        assert!(matches!(chain.get_input(2020, 2), Err(FetchError::Offline { .. })));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_default_chain_loads_each_day_once() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/settings");
+            then.status(200)
+                .body("<header><div class=\"user\">akaritakai</div></header>");
+        });
+        let mocks = (1..=5).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/2020/day/{}/input", day).as_str());
+            then.status(200)
+                .body(format!("day {}\n", day));
+        })).collect::<Vec<_>>();
+        let dir = tempdir().unwrap();
+        // Day 5 is already in the local store
+        fs::create_dir(dir.path().join("2020")).unwrap();
+        fs::write(dir.path().join("2020/5"), "day 5\n").unwrap();
+        let session_token_path = create_session_token_file();
+        let fetcher = Arc::new(create_fetcher(&server.base_url(), dir.path(),
+                                              session_token_path.path()));
+        let chain = Arc::new(default_chain(&fetcher));
+        let threads = (0..8u8).map(|i| {
+            let chain = chain.clone();
+            thread::spawn(move || {
+                (0..5u8).map(|j| {
+                    let day = (i + j) % 5 + 1;
+                    let (input, origin) = chain.get_input(2020, day).unwrap();
+                    assert_eq!(input, format!("day {}\n", day));
+                    (day, origin)
+                }).collect::<Vec<_>>()
+            })
+        }).collect::<Vec<_>>();
+        let origins = threads.into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>();
+        for day in 1..=5 {
+            let loads = origins.iter()
+                .filter(|(d, origin)| *d == day && *origin != InputOrigin::Memory)
+                .count();
+            assert_eq!(loads, 1);
+        }
+        for (day, mock) in (1..=5).zip(mocks.iter()) {
+            mock.assert_hits(if day == 5 { 0 } else { 1 });
+        }
+    }
+}
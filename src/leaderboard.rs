@@ -0,0 +1,239 @@
+use crate::clock;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// When each part of a day was solved (if it was)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayStars {
+    pub part_1: Option<SystemTime>,
+    pub part_2: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub id: u64,
+
+    // The name the member chose (if any)
+    pub name: Option<String>,
+
+    pub local_score: u64,
+    pub stars: u32,
+    pub last_star: Option<SystemTime>,
+    pub days: BTreeMap<u8, DayStars>,
+}
+
+impl Member {
+    // The name shown on the site, which is based on the id for members that haven't chosen one
+    pub fn display_name(&self) -> String {
+        match self.name.as_ref() {
+            Some(name) => name.clone(),
+            None => format!("(anonymous user #{})", self.id),
+        }
+    }
+}
+
+// A private leaderboard as given by /<year>/leaderboard/private/view/<id>.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    pub year: u16,
+    pub owner_id: u64,
+    pub members: Vec<Member>,
+}
+
+impl Leaderboard {
+    // Parses the JSON the site gives out. Depending on the year, the site gives ids and times as
+    // either numbers or strings, so both are accepted.
+    pub fn parse(json: &str) -> Result<Leaderboard, String> {
+        let value = serde_json::from_str::<Value>(json)
+            .map_err(|e| format!("The leaderboard is not valid JSON: {}", e))?;
+        let year = number(&value["event"]).ok_or("The leaderboard has no event")? as u16;
+        let owner_id = number(&value["owner_id"]).ok_or("The leaderboard has no owner")?;
+        let members = value["members"].as_object()
+            .ok_or("The leaderboard has no members")?
+            .values()
+            .map(parse_member)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Leaderboard { year, owner_id, members })
+    }
+
+    // The members in the order the site shows them: by local score, then by stars, and then by
+    // who got their last star first
+    pub fn standings(&self) -> Vec<&Member> {
+        let mut members = self.members.iter().collect::<Vec<_>>();
+        members.sort_by_key(|member| {
+            (u64::MAX - member.local_score, u32::MAX - member.stars,
+             member.last_star.unwrap_or(UNIX_EPOCH), member.id)
+        });
+        members
+    }
+
+    // The days that any member has a star for
+    pub fn days(&self) -> Vec<u8> {
+        let mut days = self.members.iter()
+            .flat_map(|member| member.days.keys().copied())
+            .collect::<Vec<_>>();
+        days.sort_unstable();
+        days.dedup();
+        days
+    }
+
+    pub fn format_standings(&self) -> String {
+        let mut lines = vec![format!("{:>3}  {:>5}  {:>5}  {}", "", "Score", "Stars", "Name")];
+        for (rank, member) in (1..).zip(self.standings()) {
+            lines.push(format!("{:>3}) {:>5}  {:>5}  {}",
+                               rank, member.local_score, member.stars, member.display_name()));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    // Shows when each member solved each part of the day (counted from when the puzzle unlocked)
+    // and how long part 2 took them after part 1. Members who solved part 2 come first, ordered
+    // by when they did, followed by those who only solved part 1.
+    pub fn format_day(&self, day: u8) -> String {
+        let unlock = clock::unlock_time(self.year, day);
+        let mut solvers = self.members.iter()
+            .filter_map(|member| member.days.get(&day).map(|stars| (member, *stars)))
+            .filter(|(_, stars)| stars.part_1.is_some())
+            .collect::<Vec<_>>();
+        solvers.sort_by_key(|(member, stars)| {
+            (stars.part_2.is_none(), stars.part_2.or(stars.part_1), member.id)
+        });
+        let elapsed = |time: Option<SystemTime>, since: SystemTime| match time {
+            Some(time) => format_elapsed(time.duration_since(since).unwrap_or_default()),
+            None => "-".to_string(),
+        };
+        let mut lines = vec![
+            format!("Day {}", day),
+            format!("  {:>9}  {:>9}  {:>9}  {}", "Part 1", "Part 2", "Delta", "Name"),
+        ];
+        for (member, stars) in solvers {
+            let delta = match (stars.part_1, stars.part_2) {
+                (Some(part_1), Some(part_2)) => elapsed(Some(part_2), part_1),
+                _ => "-".to_string(),
+            };
+            lines.push(format!("  {:>9}  {:>9}  {:>9}  {}",
+                               elapsed(stars.part_1, unlock), elapsed(stars.part_2, unlock),
+                               delta, member.display_name()));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+fn parse_member(value: &Value) -> Result<Member, String> {
+    let id = number(&value["id"]).ok_or("A member of the leaderboard has no id")?;
+    let mut days = BTreeMap::new();
+    if let Some(completed) = value["completion_day_level"].as_object() {
+        for (day, parts) in completed {
+            let day = day.parse::<u8>()
+                .map_err(|_| format!("'{}' is not a valid day on the leaderboard", day))?;
+            days.insert(day, DayStars {
+                part_1: timestamp(&parts["1"]["get_star_ts"]),
+                part_2: timestamp(&parts["2"]["get_star_ts"]),
+            });
+        }
+    }
+    Ok(Member {
+        id,
+        name: value["name"].as_str().map(|name| name.to_string()),
+        local_score: number(&value["local_score"]).unwrap_or(0),
+        stars: number(&value["stars"]).unwrap_or(0) as u32,
+        last_star: timestamp(&value["last_star_ts"]),
+        days,
+    })
+}
+
+// Reads a number that may have been given as a string
+fn number(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str()?.parse::<u64>().ok())
+}
+
+// Reads a time given in seconds since the epoch, where 0 means never
+fn timestamp(value: &Value) -> Option<SystemTime> {
+    number(value)
+        .filter(|seconds| *seconds > 0)
+        .map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+// Formats a duration as hours, minutes and seconds, e.g. '01:02:03' (or '25:00:00' past a day)
+fn format_elapsed(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::leaderboard::{format_elapsed, DayStars, Leaderboard};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn leaderboard() -> Leaderboard {
+        let json = fs::read_to_string(PathBuf::from("resources/tests/leaderboard.json")).unwrap();
+        Leaderboard::parse(&json).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let leaderboard = leaderboard();
+        assert_eq!(leaderboard.year, 2020);
+        assert_eq!(leaderboard.owner_id, 111111);
+        assert_eq!(leaderboard.members.len(), 4);
+        let member = leaderboard.members.iter().find(|member| member.id == 222222).unwrap();
+        assert_eq!(member.name, None);
+        assert_eq!(member.display_name(), "(anonymous user #222222)");
+        assert_eq!((member.local_score, member.stars), (25, 4));
+        assert_eq!(member.last_star, Some(UNIX_EPOCH + Duration::from_secs(1606973600)));
+        assert_eq!(member.days[&2], DayStars {
+            part_1: Some(UNIX_EPOCH + Duration::from_secs(1606885900)),
+            part_2: None,
+        });
+        // Ids and times given as strings are read the same way
+        let member = leaderboard.members.iter().find(|member| member.id == 111111).unwrap();
+        assert_eq!(member.days[&1].part_2, Some(UNIX_EPOCH + Duration::from_secs(1606799260)));
+        let member = leaderboard.members.iter().find(|member| member.id == 444444).unwrap();
+        assert_eq!(member.last_star, None);
+        assert!(member.days.is_empty());
+        assert_eq!(leaderboard.days(), vec![1, 2, 3]);
+        assert!(Leaderboard::parse("<!DOCTYPE html>").is_err());
+        assert!(Leaderboard::parse("{\"event\": \"2020\"}").is_err());
+    }
+
+    #[test]
+    fn test_format_standings() {
+        let lines = leaderboard().format_standings().lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![
+            "     Score  Stars  Name",
+            "  1)    40      6  akaritakai",
+            "  2)    25      4  (anonymous user #222222)",
+            "  3)    25      3  Bob",
+            "  4)     0      0  Idle",
+        ]);
+    }
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(leaderboard().format_day(1), "\
+Day 1
+     Part 1     Part 2      Delta  Name
+   00:05:12   00:07:40   00:02:28  akaritakai
+   00:06:40   00:16:40   00:10:00  (anonymous user #222222)
+   00:01:40   25:00:00   24:58:20  Bob
+");
+        assert_eq!(leaderboard().format_day(2).lines().last(),
+                   Some("   01:23:20          -          -  Bob"));
+        assert_eq!(leaderboard().format_day(4), "\
+Day 4
+     Part 1     Part 2      Delta  Name
+");
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_secs(0)), "00:00:00");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "01:02:03");
+        assert_eq!(format_elapsed(Duration::from_secs(90000)), "25:00:00");
+    }
+}
@@ -0,0 +1,39 @@
+#[macro_use] extern crate lazy_static;
+
+pub mod answer;
+pub mod answer_ledger;
+pub mod cli;
+pub mod clock;
+pub mod fetch_error;
+pub mod input_metadata;
+pub mod input_normalizer;
+pub mod input_source;
+pub mod leaderboard;
+pub mod logger;
+pub mod puzzle_description;
+pub mod puzzle_input_fetcher;
+pub mod puzzle_registry;
+pub mod puzzle;
+pub mod runner;
+pub mod session_token;
+pub mod timing;
+
+// Declares the module of each solved puzzle along with a function that registers every one of
+// them (see PuzzleRegistry::create), so that a new day only has to be added to the list below
+macro_rules! puzzles {
+    ($($module:ident::$puzzle:ident),* $(,)?) => {
+        $(pub mod $module;)*
+
+        fn register_puzzles(registry: &mut puzzle_registry::PuzzleRegistry) {
+            $($module::$puzzle::register(registry);)*
+        }
+    };
+}
+
+puzzles! {
+    puzzle01::Puzzle01,
+    puzzle02::Puzzle02,
+    puzzle03::Puzzle03,
+    puzzle04::Puzzle04,
+    puzzle05::Puzzle05,
+}
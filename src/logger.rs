@@ -0,0 +1,14 @@
+// Where problems that don't stop an operation from succeeding are reported, e.g. failing to save
+// puzzle input that was downloaded successfully
+pub trait Logger: Send + Sync {
+    fn warn(&self, message: &str);
+}
+
+// Reports warnings on standard error
+pub struct StderrLogger;
+
+impl Logger for StderrLogger {
+    fn warn(&self, message: &str) {
+        eprintln!("Warning: {}", message);
+    }
+}
@@ -1,33 +1,281 @@
-#[macro_use] extern crate lazy_static;
-
-use crate::puzzle_input_fetcher::PuzzleInputFetcher;
-use crate::puzzle::AbstractPuzzle;
-use crate::puzzle01::Puzzle01;
-use crate::puzzle02::Puzzle02;
-use crate::puzzle03::Puzzle03;
-use crate::puzzle04::Puzzle04;
-use crate::puzzle05::Puzzle05;
-
-mod puzzle_input_fetcher;
-mod puzzle;
-mod puzzle01;
-mod puzzle02;
-mod puzzle03;
-mod puzzle04;
-mod puzzle05;
+use aoc2020::answer::Answer;
+use aoc2020::answer_ledger::{AnswerLedger, Outcome};
+use aoc2020::cli::{self, Command, DescriptionFormat, InputFile, RunOptions};
+use aoc2020::input_source::{self, FileSource, InputSource, StdinSource};
+use aoc2020::puzzle_input_fetcher::{PuzzleInputFetcher, SubmissionOutcome};
+use aoc2020::puzzle_registry::PuzzleRegistry;
+use aoc2020::{clock, runner, timing};
+use aoc2020::clock::SystemClock;
+use std::sync::Arc;
+use std::{env, process};
 
 fn main() {
-    let mut fetcher = PuzzleInputFetcher::create();
-    let puzzles : Vec<Box<dyn AbstractPuzzle>> = vec![
-        Puzzle01::create(fetcher.get_puzzle_input(1).unwrap()),
-        Puzzle02::create(fetcher.get_puzzle_input(2).unwrap()),
-        Puzzle03::create(fetcher.get_puzzle_input(3).unwrap()),
-        Puzzle04::create(fetcher.get_puzzle_input(4).unwrap()),
-        Puzzle05::create(fetcher.get_puzzle_input(5).unwrap())
-    ];
-    for puzzle in puzzles.iter() {
-        let day = format!("{:02}", puzzle.get_day());
-        println!("Day {} Part 1: {}", day, puzzle.solve_part_1());
-        println!("Day {} Part 2: {}", day, puzzle.solve_part_2());
+    let args = cli::parse_args(env::args_os()).unwrap_or_else(|e| e.exit());
+    let fetcher = PuzzleInputFetcher::create_with_session_token(args.session_token.as_deref())
+        .with_fetch_policy(args.fetch_policy);
+    let fetcher = Arc::new(fetcher);
+    let input_source = input_source::default_chain(&fetcher);
+    let registry = PuzzleRegistry::create();
+    let result = match args.command {
+        Command::Run(options) => run(&registry, &input_source, options),
+        Command::Fetch { year, days, wait } => fetch(&registry, &fetcher, year, days, wait),
+        Command::Verify { year, days, refetch } => verify(&registry, &fetcher, year, days, refetch),
+        Command::List { year } => list(&registry, year),
+        Command::Submit { year, day, part, answer } => {
+            submit(&registry, &fetcher, &input_source, year, day, part, answer)
+        },
+        Command::Check { year, day, part, answer } => check(year, day, part, &answer),
+        Command::Record { year, day, part, answer, outcome } => {
+            record(year, day, part, &answer, outcome)
+        },
+        Command::Describe { year, day, format } => describe(&fetcher, year, day, format),
+        Command::Whoami => whoami(&fetcher),
+        Command::Leaderboard { year, id, days } => leaderboard(&fetcher, year, id, days),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(1);
     }
 }
+
+fn run(registry: &PuzzleRegistry, input_source: &dyn InputSource, options: RunOptions)
+    -> Result<(), String> {
+    let days = select_days(registry, options.year, options.days.clone())?;
+    if options.wait {
+        report_wait(options.year, &days);
+    }
+    // Input given on the command line is for the single selected day, and is used instead of the
+    // usual sources (so nothing is fetched or stored)
+    let given_input: Option<Box<dyn InputSource>> = match options.input.as_ref() {
+        Some(InputFile::Stdin) => Some(Box::new(StdinSource::new(options.year, days[0]))),
+        Some(InputFile::Path(path)) => Some(Box::new(FileSource::new(options.year, days[0], path))),
+        None => None,
+    };
+    let input_source = given_input.as_deref().unwrap_or(input_source);
+    let mut failed_days = Vec::new();
+    let mut timings = Vec::new();
+    runner::run_days(registry, input_source, &days, &options, |report| {
+        for warning in &report.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        for line in &report.output {
+            match line {
+                Ok(answer) => println!("{}", answer),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        if report.failed() {
+            failed_days.push(report.day);
+        }
+        timings.extend(report.timing);
+    })?;
+    if let Some(iterations) = options.iterations {
+        println!();
+        println!("{}", timing::format_report(&timings, iterations));
+    }
+    check_failures(&failed_days)
+}
+
+fn fetch(registry: &PuzzleRegistry, fetcher: &PuzzleInputFetcher, year: u16,
+         days: Option<Vec<u8>>, wait: bool) -> Result<(), String> {
+    let days = select_days(registry, year, days)?;
+    if wait {
+        report_wait(year, &days);
+    }
+    let mut failed_days = Vec::new();
+    for day in days {
+        if wait {
+            fetcher.wait_for_unlock(year, day);
+        }
+        match fetcher.get_puzzle_input(year, day) {
+            Ok(input) => println!("Day {:02}: {} bytes", day, input.len()),
+            Err(e) => {
+                eprintln!("Day {:02}: {}", day, cli::explain(&e));
+                failed_days.push(day);
+            }
+        }
+    }
+    check_failures(&failed_days)
+}
+
+fn verify(registry: &PuzzleRegistry, fetcher: &PuzzleInputFetcher, year: u16,
+          days: Option<Vec<u8>>, refetch: bool) -> Result<(), String> {
+    let days = select_days(registry, year, days)?;
+    let mut failed_days = Vec::new();
+    for day in days {
+        let status = fetcher.verify_puzzle_input(year, day);
+        if !status.is_bad() {
+            println!("Day {:02}: {}", day, status);
+        } else if !refetch {
+            eprintln!("Day {:02}: {}", day, status);
+            failed_days.push(day);
+        } else {
+            match fetcher.refetch_puzzle_input(year, day) {
+                Ok(input) => println!("Day {:02}: {} (fetched again: {} bytes)",
+                                      day, status, input.len()),
+                Err(e) => {
+                    eprintln!("Day {:02}: {} (failed to fetch again: {})",
+                              day, status, cli::explain(&e));
+                    failed_days.push(day);
+                }
+            }
+        }
+    }
+    check_failures(&failed_days)
+}
+
+// Lets the user know if any of the days haven't unlocked yet (and so will be waited for)
+fn report_wait(year: u16, days: &[u8]) {
+    let locked_days = days.iter()
+        .copied()
+        .filter(|day| clock::time_until_unlock(&SystemClock, year, *day).is_some())
+        .collect::<Vec<_>>();
+    if let Some(first) = locked_days.first() {
+        let remaining = clock::time_until_unlock(&SystemClock, year, *first).unwrap();
+        eprintln!("Waiting for {} day(s) {} to unlock. The first unlocks in {}.",
+                  year, join_days(&locked_days), clock::format_wait(remaining));
+    }
+}
+
+fn list(registry: &PuzzleRegistry, year: u16) -> Result<(), String> {
+    for day in registry.days(year) {
+        println!("Day {:02}", day);
+    }
+    Ok(())
+}
+
+fn submit(registry: &PuzzleRegistry, fetcher: &PuzzleInputFetcher, input_source: &dyn InputSource,
+          year: u16, day: u8, part: u8, answer: Option<String>) -> Result<(), String> {
+    let answer = match answer {
+        Some(answer) => Answer::parse(&answer),
+        None => {
+            select_days(registry, year, Some(vec![day]))?;
+            runner::solve_part(registry, input_source, year, day, part)?
+        }
+    };
+    // Don't bother the site with answers we already know the outcome of
+    let ledger = AnswerLedger::create();
+    let verdict = ledger.check(year, day, part, &answer)?;
+    if !verdict.should_submit() {
+        return Err(format!("Not submitting {}: {}", answer, verdict));
+    }
+    println!("Submitting {} for {} day {} part {}", answer, year, day, part);
+    let outcome = fetcher.submit(year, day, part, &answer).map_err(|e| cli::explain(&e))?;
+    if let Some(recorded_outcome) = Outcome::from_submission(outcome) {
+        if let Err(e) = ledger.record(year, day, part, &answer, recorded_outcome) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    match outcome {
+        SubmissionOutcome::Correct | SubmissionOutcome::AlreadySolved => {
+            println!("{}", outcome);
+            Ok(())
+        },
+        _ => Err(outcome.to_string()),
+    }
+}
+
+fn check(year: u16, day: u8, part: u8, answer: &str) -> Result<(), String> {
+    let answer = Answer::parse(answer);
+    let verdict = AnswerLedger::create().check(year, day, part, &answer)?;
+    if verdict.should_submit() {
+        println!("{}: {}", answer, verdict);
+        Ok(())
+    } else {
+        Err(format!("{}: {}", answer, verdict))
+    }
+}
+
+fn record(year: u16, day: u8, part: u8, answer: &str, outcome: Outcome) -> Result<(), String> {
+    let answer = Answer::parse(answer);
+    AnswerLedger::create().record(year, day, part, &answer, outcome)?;
+    println!("Recorded {} as {} for {} day {} part {}", answer, outcome, year, day, part);
+    Ok(())
+}
+
+fn describe(fetcher: &PuzzleInputFetcher, year: u16, day: u8, format: DescriptionFormat)
+    -> Result<(), String> {
+    let description = fetcher.get_puzzle_description(year, day).map_err(|e| cli::explain(&e))?;
+    match format {
+        DescriptionFormat::Markdown => print!("{}", description.to_markdown()),
+        DescriptionFormat::Text => print!("{}", description.to_text()),
+        DescriptionFormat::Examples => {
+            for (i, example) in description.examples().iter().enumerate() {
+                println!("Example {} (part {}):", i + 1, example.part);
+                print!("{}", example.input);
+                if !example.input.ends_with('\n') {
+                    println!();
+                }
+                println!();
+            }
+            for answer in description.highlighted_answers() {
+                println!("Highlighted (part {}): {}", answer.part, answer.answer);
+            }
+        },
+    }
+    Ok(())
+}
+
+fn whoami(fetcher: &PuzzleInputFetcher) -> Result<(), String> {
+    let account = fetcher.check_session().map_err(|e| cli::explain(&e))?;
+    println!("Logged in as {} (session token from {})", account.name, account.token_source);
+    Ok(())
+}
+
+fn leaderboard(fetcher: &PuzzleInputFetcher, year: u16, id: u64, days: Option<Vec<u8>>)
+    -> Result<(), String> {
+    let leaderboard = fetcher.get_private_leaderboard(year, id).map_err(|e| cli::explain(&e))?;
+    print!("{}", leaderboard.format_standings());
+    for day in days.unwrap_or_else(|| leaderboard.days()) {
+        println!();
+        print!("{}", leaderboard.format_day(day));
+    }
+    Ok(())
+}
+
+// Returns the requested days (or every available day if none were requested), refusing any day
+// that does not have a solution yet.
+fn select_days(registry: &PuzzleRegistry, year: u16, days: Option<Vec<u8>>)
+    -> Result<Vec<u8>, String> {
+    match days {
+        None if registry.days(year).is_empty() => {
+            Err(format!("No solutions are available for {}. Available years: {}",
+                        year, join_years(&registry.years())))
+        },
+        None => Ok(registry.days(year)),
+        Some(days) => {
+            let missing = days.iter()
+                .copied()
+                .filter(|day| !registry.is_implemented(year, *day))
+                .collect::<Vec<_>>();
+            if missing.is_empty() {
+                Ok(days)
+            } else {
+                Err(format!("No solution is available for {} day(s) {}. Available days: {}",
+                            year, join_days(&missing), join_days(&registry.days(year))))
+            }
+        }
+    }
+}
+
+fn check_failures(failed_days: &[u8]) -> Result<(), String> {
+    if failed_days.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Failed on day(s) {}", join_days(failed_days)))
+    }
+}
+
+fn join_days(days: &[u8]) -> String {
+    days.iter()
+        .map(|day| day.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn join_years(years: &[u16]) -> String {
+    years.iter()
+        .map(|year| year.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,5 +1,76 @@
-pub trait AbstractPuzzle {
+use crate::answer::Answer;
+use std::error::Error;
+use std::fmt;
+
+// A puzzle solution. The input is parsed once and the parsed form is shared by both parts.
+pub trait Puzzle {
+    type Parsed: Send + Sync;
+
+    const DAY: u8;
+
+    fn parse(input: &str) -> Result<Self::Parsed>;
+    fn part1(parsed: &Self::Parsed) -> Result<Answer>;
+    fn part2(parsed: &Self::Parsed) -> Result<Answer>;
+}
+
+// A puzzle whose input has already been parsed. Used as a trait object so that puzzles with
+// different parsed types can be run side by side (and on different threads).
+pub trait AbstractPuzzle: Send + Sync {
     fn get_day(&self) -> u8;
-    fn solve_part_1(&self) -> String;
-    fn solve_part_2(&self) -> String;
+    fn solve_part_1(&self) -> Result<Answer>;
+    fn solve_part_2(&self) -> Result<Answer>;
+}
+
+pub struct ParsedPuzzle<P: Puzzle> {
+    parsed: P::Parsed,
+}
+
+impl<P: Puzzle + 'static> ParsedPuzzle<P> {
+    // Runs the parse phase of the puzzle, returning a puzzle that is ready to be solved
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        Ok(Box::new(ParsedPuzzle::<P> {
+            parsed: P::parse(input)?
+        }))
+    }
+}
+
+impl<P: Puzzle> AbstractPuzzle for ParsedPuzzle<P> {
+    fn get_day(&self) -> u8 {
+        P::DAY
+    }
+
+    fn solve_part_1(&self) -> Result<Answer> {
+        P::part1(&self.parsed)
+    }
+
+    fn solve_part_2(&self) -> Result<Answer> {
+        P::part2(&self.parsed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    // The input could not be parsed. Lines and columns are 1-based.
+    Parse { line: usize, column: usize, message: String },
+
+    // The input was understood, but nothing in it satisfies the puzzle
+    NoSolution,
+
+    // The input was understood, but it does not have the shape the puzzle expects
+    BadInput(String),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Parse { line, column, message } =>
+                write!(f, "Failed to parse input at line {}, column {}: {}", line, column, message),
+            SolveError::NoSolution => write!(f, "Unable to find the solution"),
+            SolveError::BadInput(message) => write!(f, "Input has an unexpected shape: {}", message),
+        }
+    }
 }
+
+impl Error for SolveError {}
+
+pub type Result<T> = std::result::Result<T, SolveError>;
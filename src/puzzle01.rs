@@ -1,35 +1,47 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::answer::Answer;
+use crate::puzzle::{AbstractPuzzle, ParsedPuzzle, Puzzle, Result, SolveError};
+use crate::puzzle_registry::PuzzleRegistry;
 use std::collections::HashSet;
 use std::cmp::Ordering;
 
-pub struct Puzzle01 {
-    input: String,
-}
+pub struct Puzzle01;
+
+impl Puzzle for Puzzle01 {
+    // The expense report entries in ascending order
+    type Parsed = Vec<u32>;
+
+    const DAY: u8 = 1;
 
-impl AbstractPuzzle for Puzzle01 {
-    fn get_day(&self) -> u8 {
-        1
+    fn parse(input: &str) -> Result<Vec<u32>> {
+        let mut numbers = input.lines()
+            .enumerate()
+            .map(|(i, line)| line.parse::<u32>().map_err(|e| SolveError::Parse {
+                line: i + 1,
+                column: line.find(|c: char| !c.is_ascii_digit()).unwrap_or(0) + 1,
+                message: format!("Expected an expense report entry but got '{}': {}", line, e),
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        numbers.sort_unstable();
+        Ok(numbers)
     }
 
-    fn solve_part_1(&self) -> String {
-        let mut numbers : HashSet<u32> = HashSet::new();
-        for line in self.input.lines() {
-            let n1 = line.parse::<u32>().unwrap();
-            let n2 = 2020 - n1;
-            if numbers.contains(&n2) {
-                return (n1 * n2).to_string();
+    fn part1(numbers: &Vec<u32>) -> Result<Answer> {
+        let mut seen : HashSet<u32> = HashSet::new();
+        for &n1 in numbers {
+            if n1 <= 2020 {
+                let n2 = 2020 - n1;
+                if seen.contains(&n2) {
+                    return Ok((n1 * n2).into());
+                }
             }
-            numbers.insert(n1);
+            seen.insert(n1);
         }
-        panic!("Unable to find the solution");
+        Err(SolveError::NoSolution)
     }
 
-    fn solve_part_2(&self) -> String {
-        let mut numbers: Vec<u32> = self.input.lines()
-            .map(|line| line.parse::<u32>().unwrap())
-            .collect();
-        numbers.sort_unstable();
-        for i in 0..numbers.len() - 2 {
+    fn part2(numbers: &Vec<u32>) -> Result<Answer> {
+        let numbers: Vec<u32> = numbers.iter().copied().filter(|n| *n <= 2020).collect();
+        for i in 0..numbers.len().saturating_sub(2) {
             let n1 = numbers[i];
             let mut j = i + 1;
             let mut k = numbers.len() - 1;
@@ -40,204 +52,221 @@ impl AbstractPuzzle for Puzzle01 {
                 match sum.cmp(&2020) {
                     Ordering::Less => j += 1,
                     Ordering::Greater => k -= 1,
-                    Ordering::Equal => return (n1 * n2 * n3).to_string()
+                    Ordering::Equal => return Ok((n1 * n2 * n3).into())
                 }
             }
         }
-        panic!("Unable to find the solution");
+        Err(SolveError::NoSolution)
     }
 }
 
 impl Puzzle01 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle01 {
-            input: input.to_string()
-        })
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        ParsedPuzzle::<Puzzle01>::create(input)
+    }
+
+    pub fn register(registry: &mut PuzzleRegistry) {
+        registry.register(2020, 1, Puzzle01::create);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::answer::Answer;
+    use crate::input_normalizer::normalize;
+    use crate::puzzle::SolveError;
     use crate::puzzle01::Puzzle01;
     use std::path::PathBuf;
     use std::fs;
 
     #[test]
     fn test_part_1_example_1() {
-        let input = vec!["1721", "979", "366", "299", "675", "1456"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "514579");
+        let input = ["1721", "979", "366", "299", "675", "1456"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(514579));
     }
 
     #[test]
     fn test_part_1_example_2() {
         // Ensure duplicate entries are handled
-        let input = vec!["1000", "1010", "1010"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1020100");
+        let input = ["1000", "1010", "1010"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1020100));
     }
 
     #[test]
     fn test_part_1_example_3() {
         // Ensure duplicate entries are handled (alternate order)
-        let input = vec!["1010", "1000", "1010"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1020100");
+        let input = ["1010", "1000", "1010"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1020100));
     }
 
     #[test]
     fn test_part_1_example_4() {
         // Ensure a size of 2 is handled
-        let input = vec!["1009", "1011"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1020099");
+        let input = ["1009", "1011"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1020099));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_1_example_5() {
         // Ensure a size of 1 fails
-        let input = vec!["2020"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_1();
+        let input = ["2020"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1(), Err(SolveError::NoSolution));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_1_example_6() {
         // Ensure an input that does not satisfy part 1 fails
-        let input = vec!["1010", "1011"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_1();
+        let input = ["1010", "1011"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1(), Err(SolveError::NoSolution));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_1_example_7() {
         // Ensure an input that does not satisfy part 1 fails (alternate)
-        let input = vec!["1721", "979", "366", "675", "1456"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_1();
+        let input = ["1721", "979", "366", "675", "1456"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1(), Err(SolveError::NoSolution));
     }
 
     #[test]
     fn test_part_1_example_8() {
         // Ensure a size of 2 with duplicates are handled
-        let input = vec!["1010", "1010"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1020100");
+        let input = ["1010", "1010"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1020100));
     }
 
     #[test]
     fn test_part_1_example_9() {
         // Ensure a pass if the subset is on the extremes of the array
-        let input = vec!["1721", "979", "366", "675", "1456", "299"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "514579");
+        let input = ["1721", "979", "366", "675", "1456", "299"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(514579));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_1_example_10() {
         // Ensure an empty input fails
-        let puzzle = Puzzle01::create("");
-        puzzle.solve_part_1();
+        let puzzle = Puzzle01::create("").unwrap();
+        assert_eq!(puzzle.solve_part_1(), Err(SolveError::NoSolution));
+    }
+
+    #[test]
+    fn test_part_1_example_11() {
+        // Ensure an entry that is not a number fails with its position
+        let input = ["1721", "97x", "366"];
+        let puzzle = Puzzle01::create(&input.join("\n"));
+        match puzzle.err() {
+            Some(SolveError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            error => panic!("Expected a parse error but got {:?}", error)
+        }
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap();
-        let puzzle = Puzzle01::create(input.as_str());
-        assert_eq!(puzzle.solve_part_1(), "440979");
+        let puzzle = Puzzle01::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(440979));
     }
 
     #[test]
     fn test_part_2_example_1() {
-        let input = vec!["1721", "979", "366", "299", "675", "1456"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "241861950");
+        let input = ["1721", "979", "366", "299", "675", "1456"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(241861950));
     }
 
     #[test]
     fn test_part_2_example_2() {
         // Ensure duplicate entries are handled
-        let input = vec!["672", "672", "676"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "305270784");
+        let input = ["672", "672", "676"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(305270784));
     }
 
     #[test]
     fn test_part_2_example_3() {
         // Ensure duplicate entries are handled (alternate order)
-        let input = vec!["672", "676", "672"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "305270784");
+        let input = ["672", "676", "672"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(305270784));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_2_example_4() {
         // Ensure a size of 2 fails
-        let input = vec!["1009", "1011"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_2();
+        let input = ["1009", "1011"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2(), Err(SolveError::NoSolution));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_2_example_5() {
         // Ensure a size of 1 fails
-        let input = vec!["2020"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_2();
+        let input = ["2020"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2(), Err(SolveError::NoSolution));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_2_example_6() {
         // Ensure an input that does not satisfy part 2 fails
-        let input = vec!["500", "501", "1020"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_2();
+        let input = ["500", "501", "1020"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2(), Err(SolveError::NoSolution));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_2_example_7() {
         // Ensure an input that does not satisfy part 2 fails (alternate)
-        let input = vec!["1721", "979", "366", "299", "1456"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        puzzle.solve_part_2();
+        let input = ["1721", "979", "366", "299", "1456"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2(), Err(SolveError::NoSolution));
     }
 
     #[test]
     fn test_part_2_example_8() {
         // Ensure a size of 4 with duplicates are handled
-        let input = vec!["672", "500", "672", "676"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "305270784");
+        let input = ["672", "500", "672", "676"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(305270784));
     }
 
     #[test]
     fn test_part_2_example_9() {
         // Ensure a pass if the subset is on the extremes of the array
-        let input = vec!["979", "1721", "1456", "366", "299", "675"];
-        let puzzle = Puzzle01::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "241861950");
+        let input = ["979", "1721", "1456", "366", "299", "675"];
+        let puzzle = Puzzle01::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(241861950));
     }
 
     #[test]
-    #[should_panic]
     fn test_part_2_example_10() {
         // Ensure an empty input fails
-        let puzzle = Puzzle01::create("");
-        puzzle.solve_part_2();
+        let puzzle = Puzzle01::create("").unwrap();
+        assert_eq!(puzzle.solve_part_2(), Err(SolveError::NoSolution));
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap();
-        let puzzle = Puzzle01::create(input.as_str());
-        assert_eq!(puzzle.solve_part_2(), "82498112");
+        let puzzle = Puzzle01::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(82498112));
+    }
+
+    #[test]
+    fn test_solve_with_crlf_input() {
+        // Input saved on Windows is normalized before it is parsed
+        let input = fs::read_to_string(PathBuf::from("resources/tests/1")).unwrap()
+            .replace('\n', "\r\n");
+        let puzzle = Puzzle01::create(&normalize(&input).input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(440979));
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(82498112));
     }
 }
@@ -1,149 +1,185 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::answer::Answer;
+use crate::puzzle::{AbstractPuzzle, ParsedPuzzle, Puzzle, Result, SolveError};
+use crate::puzzle_registry::PuzzleRegistry;
 use regex::Regex;
 
-pub struct Puzzle02 {
-    input: String,
-}
+pub struct Puzzle02;
+
+impl Puzzle for Puzzle02 {
+    // The (lower, upper, letter, password) entries of the password database
+    type Parsed = Vec<(usize, usize, char, String)>;
 
-impl AbstractPuzzle for Puzzle02 {
-    fn get_day(&self) -> u8 {
-        2
+    const DAY: u8 = 2;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| parse(line).ok_or_else(|| SolveError::Parse {
+                line: i + 1,
+                column: 1,
+                message: format!("Expected a policy like '1-3 a: abcde' but got '{}'", line),
+            }))
+            .collect()
     }
 
-    fn solve_part_1(&self) -> String {
-        self.input.lines().into_iter()
-            .map(|line| parse(line))
+    fn part1(entries: &Self::Parsed) -> Result<Answer> {
+        Ok(entries.iter()
             .filter(|(lower, upper, letter, password)| {
                 let count = password.matches(*letter).count();
                 count >= *lower && count <= *upper
             })
             .count()
-            .to_string()
+            .into())
     }
 
-    fn solve_part_2(&self) -> String {
-        self.input.lines().into_iter()
-            .map(|line| parse(line))
+    fn part2(entries: &Self::Parsed) -> Result<Answer> {
+        Ok(entries.iter()
             .filter(|(lower, upper, letter, password)|
                   (lower - 1 < password.len() && password.as_bytes()[lower - 1] as char == *letter)
                 ^ (upper - 1 < password.len() && password.as_bytes()[upper - 1] as char == *letter))
             .count()
-            .to_string()
+            .into())
     }
 }
 
 impl Puzzle02 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle02 {
-            input: input.to_string()
-        })
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        ParsedPuzzle::<Puzzle02>::create(input)
+    }
+
+    pub fn register(registry: &mut PuzzleRegistry) {
+        registry.register(2020, 2, Puzzle02::create);
     }
 }
 
-fn parse(line: &str) -> (usize, usize, char, String) {
+fn parse(line: &str) -> Option<(usize, usize, char, String)> {
     lazy_static! {
             static ref RE: Regex = Regex::new(r"^(\d+)-(\d+) ([a-z]): ([a-z]+)$").unwrap();
     }
-    let cap = RE.captures(line).unwrap();
-    (cap[1].parse::<usize>().unwrap(),
-     cap[2].parse::<usize>().unwrap(),
-     cap[3].parse::<char>().unwrap(),
-     cap[4].to_string())
+    let cap = RE.captures(line)?;
+    let lower = cap[1].parse::<usize>().ok().filter(|n| *n > 0)?;
+    let upper = cap[2].parse::<usize>().ok().filter(|n| *n > 0)?;
+    Some((lower, upper, cap[3].parse::<char>().unwrap(), cap[4].to_string()))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::answer::Answer;
+    use crate::input_normalizer::normalize;
+    use crate::puzzle::SolveError;
     use crate::puzzle02::Puzzle02;
     use std::path::PathBuf;
     use std::fs;
 
     #[test]
     fn test_part_1_example_1() {
-        let input = vec![
+        let input = [
             "1-3 a: abcde",
             "1-3 b: cdefg",
             "2-9 c: ccccccccc"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
     fn test_part_1_example_2() {
-        let input = vec![
+        let input = [
             "1-3 a: abcde"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1));
     }
 
     #[test]
     fn test_part_1_example_3() {
-        let input = vec![
+        let input = [
             "1-3 b: cdefg"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "0");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(0));
     }
 
     #[test]
     fn test_part_1_example_4() {
-        let input = vec![
+        let input = [
             "2-9 c: ccccccccc"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_1(), "1");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(1));
+    }
+
+    #[test]
+    fn test_part_1_example_5() {
+        let input = [
+            "1-3 a: abcde",
+            "1-3 b cdefg"
+        ];
+        match Puzzle02::create(&input.join("\n")).err() {
+            Some(SolveError::Parse { line, .. }) => assert_eq!(line, 2),
+            error => panic!("Expected a parse error but got {:?}", error)
+        }
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/2")).unwrap();
-        let puzzle = Puzzle02::create(input.as_str());
-        assert_eq!(puzzle.solve_part_1(), "434");
+        let puzzle = Puzzle02::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(434));
     }
 
     #[test]
     fn test_part_2_example_1() {
-        let input = vec![
+        let input = [
             "1-3 a: abcde",
             "1-3 b: cdefg",
             "2-9 c: ccccccccc"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "1");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(1));
     }
 
     #[test]
     fn test_part_2_example_2() {
-        let input = vec![
+        let input = [
             "1-3 a: abcde"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "1");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(1));
     }
 
     #[test]
     fn test_part_2_example_3() {
-        let input = vec![
+        let input = [
             "1-3 b: cdefg"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "0");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(0));
     }
 
     #[test]
     fn test_part_2_example_4() {
-        let input = vec![
+        let input = [
             "2-9 c: ccccccccc"
         ];
-        let puzzle = Puzzle02::create(&input.join("\n"));
-        assert_eq!(puzzle.solve_part_2(), "0");
+        let puzzle = Puzzle02::create(&input.join("\n")).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(0));
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/2")).unwrap();
-        let puzzle = Puzzle02::create(input.as_str());
-        assert_eq!(puzzle.solve_part_2(), "509");
+        let puzzle = Puzzle02::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(509));
+    }
+
+    #[test]
+    fn test_solve_with_crlf_input() {
+        // Input saved on Windows is normalized before it is parsed
+        let input = fs::read_to_string(PathBuf::from("resources/tests/2")).unwrap()
+            .replace('\n', "\r\n");
+        let puzzle = Puzzle02::create(&normalize(&input).input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(434));
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(509));
     }
 }
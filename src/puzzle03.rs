@@ -1,47 +1,79 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::answer::Answer;
+use crate::puzzle::{AbstractPuzzle, ParsedPuzzle, Puzzle, Result, SolveError};
+use crate::puzzle_registry::PuzzleRegistry;
 
-pub struct Puzzle03 {
-    input: String,
-}
+pub struct Puzzle03;
+
+impl Puzzle for Puzzle03 {
+    // The rows of the map, where each square is true if it contains a tree
+    type Parsed = Vec<Vec<bool>>;
 
-impl AbstractPuzzle for Puzzle03 {
-    fn get_day(&self) -> u8 {
-        3
+    const DAY: u8 = 3;
+
+    // Parses the map, ensuring it only contains open squares and trees and that every row has the
+    // same width
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        let mut rows: Vec<Vec<bool>> = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if let Some(j) = line.find(|c| c != '.' && c != '#') {
+                let c = line[j..].chars().next().unwrap();
+                return Err(SolveError::Parse {
+                    line: i + 1,
+                    column: j + 1,
+                    message: format!("Expected '.' or '#' but got '{}'", c),
+                });
+            }
+            match rows.first() {
+                None if line.is_empty() => return Err(SolveError::BadInput(
+                    format!("Row {} of the map is empty", i + 1))),
+                Some(first) if first.len() != line.len() => return Err(SolveError::BadInput(
+                    format!("Row {} of the map has width {} but earlier rows have width {}",
+                            i + 1, line.len(), first.len()))),
+                _ => rows.push(line.chars().map(|c| c == '#').collect()),
+            }
+        }
+        Ok(rows)
     }
 
-    fn solve_part_1(&self) -> String {
-        self.trees_on_slope(3, 1).to_string()
+    fn part1(map: &Self::Parsed) -> Result<Answer> {
+        Ok(trees_on_slope(map, 3, 1).into())
     }
 
-    fn solve_part_2(&self) -> String {
+    fn part2(map: &Self::Parsed) -> Result<Answer> {
         let mut count = 1;
-        count *= self.trees_on_slope(1, 1);
-        count *= self.trees_on_slope(3, 1);
-        count *= self.trees_on_slope(5, 1);
-        count *= self.trees_on_slope(7, 1);
-        count *= self.trees_on_slope(1, 2);
-        count.to_string()
+        count *= trees_on_slope(map, 1, 1);
+        count *= trees_on_slope(map, 3, 1);
+        count *= trees_on_slope(map, 5, 1);
+        count *= trees_on_slope(map, 7, 1);
+        count *= trees_on_slope(map, 1, 2);
+        Ok(count.into())
     }
 }
 
 impl Puzzle03 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle03 {
-            input: input.to_string()
-        })
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        ParsedPuzzle::<Puzzle03>::create(input)
     }
 
-    fn trees_on_slope(&self, x: usize, y: usize) -> usize {
-        self.input.lines()
-            .step_by(y)
-            .enumerate()
-            .filter(|(i, line)| line.chars().cycle().nth(i * x) == Some('#'))
-            .count()
+    pub fn register(registry: &mut PuzzleRegistry) {
+        registry.register(2020, 3, Puzzle03::create);
     }
 }
 
+// The map repeats to the right, so the column wraps around the width of each row
+fn trees_on_slope(map: &[Vec<bool>], x: usize, y: usize) -> usize {
+    map.iter()
+        .step_by(y)
+        .enumerate()
+        .filter(|(i, row)| row[(i * x) % row.len()])
+        .count()
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::answer::Answer;
+    use crate::input_normalizer::normalize;
+    use crate::puzzle::SolveError;
     use crate::puzzle03::Puzzle03;
     use std::path::PathBuf;
     use std::fs;
@@ -59,15 +91,29 @@ mod tests {
                            #.##...#...\n\
                            #...##....#\n\
                            .#..#...#.#\n";
-        let puzzle = Puzzle03::create(input);
-        assert_eq!(puzzle.solve_part_1(), "7");
+        let puzzle = Puzzle03::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(7));
+    }
+
+    #[test]
+    fn test_part_1_example_2() {
+        match Puzzle03::create("..##.\n#..X.\n").err() {
+            Some(SolveError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 4)),
+            error => panic!("Expected a parse error but got {:?}", error)
+        }
+    }
+
+    #[test]
+    fn test_part_1_example_3() {
+        let puzzle = Puzzle03::create("..##.\n#...\n");
+        assert!(matches!(puzzle.err(), Some(SolveError::BadInput(_))));
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/3")).unwrap();
-        let puzzle = Puzzle03::create(input.as_str());
-        assert_eq!(puzzle.solve_part_1(), "284");
+        let puzzle = Puzzle03::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(284));
     }
 
     #[test]
@@ -83,14 +129,24 @@ mod tests {
                            #.##...#...\n\
                            #...##....#\n\
                            .#..#...#.#\n";
-        let puzzle = Puzzle03::create(input);
-        assert_eq!(puzzle.solve_part_2(), "336");
+        let puzzle = Puzzle03::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(336));
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/3")).unwrap();
-        let puzzle = Puzzle03::create(input.as_str());
-        assert_eq!(puzzle.solve_part_2(), "3510149120");
+        let puzzle = Puzzle03::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(3510149120));
+    }
+
+    #[test]
+    fn test_solve_with_crlf_input() {
+        // Input saved on Windows is normalized before it is parsed
+        let input = fs::read_to_string(PathBuf::from("resources/tests/3")).unwrap()
+            .replace('\n', "\r\n");
+        let puzzle = Puzzle03::create(&normalize(&input).input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(284));
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(3510149120));
     }
 }
@@ -1,44 +1,51 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::answer::Answer;
+use crate::puzzle::{AbstractPuzzle, ParsedPuzzle, Puzzle, Result};
+use crate::puzzle_registry::PuzzleRegistry;
 use std::collections::HashMap;
 use regex::Regex;
 
-pub struct Puzzle04 {
-    input: String,
-}
+pub struct Puzzle04;
+
+impl Puzzle for Puzzle04 {
+    // The recognized fields of each passport
+    type Parsed = Vec<HashMap<String, String>>;
 
-impl AbstractPuzzle for Puzzle04 {
-    fn get_day(&self) -> u8 {
-        4
+    const DAY: u8 = 4;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        Ok(input.split("\n\n")
+            .map(parse_passport)
+            .collect())
     }
 
-    fn solve_part_1(&self) -> String {
-        self.input.split("\n\n")
-            .map(|passport| parse_passport(passport))
+    fn part1(passports: &Self::Parsed) -> Result<Answer> {
+        Ok(passports.iter()
             .filter(|passport| has_required_fields(passport))
             .count()
-            .to_string()
+            .into())
     }
 
-    fn solve_part_2(&self) -> String {
-        self.input.split("\n\n")
-            .map(|passport| parse_passport(passport))
+    fn part2(passports: &Self::Parsed) -> Result<Answer> {
+        Ok(passports.iter()
             .filter(|passport| has_required_fields(passport) && all_values_valid(passport))
             .count()
-            .to_string()
+            .into())
     }
 }
 
 impl Puzzle04 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle04 {
-            input: input.to_string()
-        })
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        ParsedPuzzle::<Puzzle04>::create(input)
+    }
+
+    pub fn register(registry: &mut PuzzleRegistry) {
+        registry.register(2020, 4, Puzzle04::create);
     }
 }
 
 fn parse_passport(passport: &str) -> HashMap<String, String> {
     passport.split_whitespace()
-        .filter_map(|entry| parse_passport_entry(entry))
+        .filter_map(parse_passport_entry)
         .collect::<HashMap<_, _>>()
 }
 
@@ -101,6 +108,8 @@ fn in_range(number: &str, start_inclusive: usize, end_inclusive: usize) -> bool
 
 #[cfg(test)]
 mod tests {
+    use crate::answer::Answer;
+    use crate::input_normalizer::normalize;
     use crate::puzzle04::{Puzzle04, is_value_valid};
     use std::path::PathBuf;
     use std::fs;
@@ -117,8 +126,8 @@ mod tests {
                 hgt:179cm\n\n\
                 hcl:#cfa07d eyr:2025 pid:166559648\n\
                 iyr:2011 ecl:brn hgt:59in";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
@@ -133,8 +142,8 @@ mod tests {
                 hgt:179cm\n\n\
                 hcl:#cfa07d eyr:2025 pid:166559648\n\
                 iyr:2011 ecl:brn hgt:59in";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
@@ -149,8 +158,8 @@ mod tests {
                 hgt:179cm\n\n\
                 hcl:#cfa07d eyr:2025 pid:166559648\n\
                 iyr:2011 ecl:brn hgt:59in";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
@@ -165,8 +174,8 @@ mod tests {
                 hgt:179cm\n\n\
                 hcl:#cfa07d eyr:2025 pid:166559648\n\
                 iyr:2011 ecl:brn hgt:59in";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
@@ -181,15 +190,15 @@ mod tests {
                 hgt:179cm\n\n\
                 hcl:#cfa07d eyr:2025 pid:166559648\n\
                 iyr:2011 ecl:brn hgt:59in";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_1(), "2");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(2));
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/4")).unwrap();
-        let puzzle = Puzzle04::create(input.as_str());
-        assert_eq!(puzzle.solve_part_1(), "250");
+        let puzzle = Puzzle04::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(250));
     }
 
     #[test]
@@ -221,8 +230,8 @@ mod tests {
                 hgt:59cm ecl:zzz\n\
                 eyr:2038 hcl:74454a iyr:2023\n\
                 pid:3556412378 byr:2007";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_2(), "0");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(0));
     }
 
     #[test]
@@ -236,14 +245,24 @@ mod tests {
                 pid:545766238 ecl:hzl\n\
                 eyr:2022\n\n\
                 iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
-        let puzzle = Puzzle04::create(input);
-        assert_eq!(puzzle.solve_part_2(), "4");
+        let puzzle = Puzzle04::create(input).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(4));
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/4")).unwrap();
-        let puzzle = Puzzle04::create(input.as_str());
-        assert_eq!(puzzle.solve_part_2(), "158");
+        let puzzle = Puzzle04::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(158));
+    }
+
+    #[test]
+    fn test_solve_with_crlf_input() {
+        // Input saved on Windows is normalized before it is parsed
+        let input = fs::read_to_string(PathBuf::from("resources/tests/4")).unwrap()
+            .replace('\n', "\r\n");
+        let puzzle = Puzzle04::create(&normalize(&input).input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(250));
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(158));
     }
 }
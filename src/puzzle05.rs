@@ -1,38 +1,56 @@
-use crate::puzzle::AbstractPuzzle;
+use crate::answer::Answer;
+use crate::puzzle::{AbstractPuzzle, ParsedPuzzle, Puzzle, Result, SolveError};
+use crate::puzzle_registry::PuzzleRegistry;
 use std::collections::BTreeSet;
 
-pub struct Puzzle05 {
-    input: String,
-}
+pub struct Puzzle05;
+
+impl Puzzle for Puzzle05 {
+    // The seat IDs of every boarding pass
+    type Parsed = BTreeSet<usize>;
 
-impl AbstractPuzzle for Puzzle05 {
-    fn get_day(&self) -> u8 {
-        5
+    const DAY: u8 = 5;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                if let Some(j) = line.find(|c| !matches!(c, 'F' | 'B' | 'L' | 'R')) {
+                    let c = line[j..].chars().next().unwrap();
+                    return Err(SolveError::Parse {
+                        line: i + 1,
+                        column: j + 1,
+                        message: format!("Expected one of 'F', 'B', 'L' or 'R' but got '{}'", c),
+                    });
+                }
+                Ok(seat_id(line))
+            })
+            .collect()
     }
 
-    fn solve_part_1(&self) -> String {
-        self.input.lines()
-            .map(|line| seat_id(line))
-            .max()
-            .unwrap()
-            .to_string()
+    fn part1(seat_ids: &Self::Parsed) -> Result<Answer> {
+        seat_ids.iter()
+            .last()
+            .map(|seat_id| Answer::from(*seat_id))
+            .ok_or(SolveError::NoSolution)
     }
 
-    fn solve_part_2(&self) -> String {
-        let seat_ids = self.input.lines()
-            .map(|line| seat_id(line))
-            .collect::<BTreeSet<_>>();
-        let min = *seat_ids.iter().next().unwrap();
-        let max = *seat_ids.iter().last().unwrap();
-        (min..max).find(|seat_id| !seat_ids.contains(seat_id)).unwrap().to_string()
+    fn part2(seat_ids: &Self::Parsed) -> Result<Answer> {
+        let min = *seat_ids.iter().next().ok_or(SolveError::NoSolution)?;
+        let max = *seat_ids.iter().last().ok_or(SolveError::NoSolution)?;
+        (min..max).find(|seat_id| !seat_ids.contains(seat_id))
+            .map(Answer::from)
+            .ok_or(SolveError::NoSolution)
     }
 }
 
 impl Puzzle05 {
-    pub fn create(input: &str) -> Box<dyn AbstractPuzzle> {
-        Box::new(Puzzle05 {
-            input: input.to_string()
-        })
+    pub fn create(input: &str) -> Result<Box<dyn AbstractPuzzle>> {
+        ParsedPuzzle::<Puzzle05>::create(input)
+    }
+
+    pub fn register(registry: &mut PuzzleRegistry) {
+        registry.register(2020, 5, Puzzle05::create);
     }
 }
 
@@ -47,32 +65,35 @@ fn seat_id(boarding_pass: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
+    use crate::answer::Answer;
+    use crate::input_normalizer::normalize;
+    use crate::puzzle::SolveError;
     use crate::puzzle05::Puzzle05;
     use std::path::PathBuf;
     use std::fs;
 
     #[test]
     fn test_part_1_example_1() {
-        let puzzle = Puzzle05::create("FBFBBFFRLR");
-        assert_eq!(puzzle.solve_part_1(), "357");
+        let puzzle = Puzzle05::create("FBFBBFFRLR").unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(357));
     }
 
     #[test]
     fn test_part_1_example_2() {
-        let puzzle = Puzzle05::create("BFFFBBFRRR");
-        assert_eq!(puzzle.solve_part_1(), "567");
+        let puzzle = Puzzle05::create("BFFFBBFRRR").unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(567));
     }
 
     #[test]
     fn test_part_1_example_3() {
-        let puzzle = Puzzle05::create("FFFBBBFRRR");
-        assert_eq!(puzzle.solve_part_1(), "119");
+        let puzzle = Puzzle05::create("FFFBBBFRRR").unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(119));
     }
 
     #[test]
     fn test_part_1_example_4() {
-        let puzzle = Puzzle05::create("BBFFBBFRLL");
-        assert_eq!(puzzle.solve_part_1(), "820");
+        let puzzle = Puzzle05::create("BBFFBBFRLL").unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(820));
     }
 
     #[test]
@@ -81,21 +102,45 @@ mod tests {
             FBFBBFFRLR\n\
             BFFFBBFRRR\n\
             BBFFBBFRLL\n\
-            FFFBBBFRRR");
-        assert_eq!(puzzle.solve_part_1(), "820");
+            FFFBBBFRRR").unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(820));
+    }
+
+    #[test]
+    fn test_part_1_example_6() {
+        match Puzzle05::create("FBFBBFFRLR\nBFFFBBXRRR").err() {
+            Some(SolveError::Parse { line, column, .. }) => assert_eq!((line, column), (2, 7)),
+            error => panic!("Expected a parse error but got {:?}", error)
+        }
+    }
+
+    #[test]
+    fn test_part_1_example_7() {
+        let puzzle = Puzzle05::create("").unwrap();
+        assert_eq!(puzzle.solve_part_1(), Err(SolveError::NoSolution));
     }
 
     #[test]
     fn test_solve_part_1() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/5")).unwrap();
-        let puzzle = Puzzle05::create(input.as_str());
-        assert_eq!(puzzle.solve_part_1(), "906");
+        let puzzle = Puzzle05::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(906));
     }
 
     #[test]
     fn test_solve_part_2() {
         let input = fs::read_to_string(PathBuf::from("resources/tests/5")).unwrap();
-        let puzzle = Puzzle05::create(input.as_str());
-        assert_eq!(puzzle.solve_part_2(), "519");
+        let puzzle = Puzzle05::create(input.as_str()).unwrap();
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(519));
+    }
+
+    #[test]
+    fn test_solve_with_crlf_input() {
+        // Input saved on Windows is normalized before it is parsed
+        let input = fs::read_to_string(PathBuf::from("resources/tests/5")).unwrap()
+            .replace('\n', "\r\n");
+        let puzzle = Puzzle05::create(&normalize(&input).input).unwrap();
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(906));
+        assert_eq!(puzzle.solve_part_2().unwrap(), Answer::Unsigned(519));
     }
 }
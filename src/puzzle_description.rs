@@ -0,0 +1,251 @@
+use regex::{Captures, Regex};
+
+// An example input given in the puzzle description
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    pub part: u8,
+    pub input: String,
+}
+
+// A value highlighted in the puzzle description. These are usually the answers to the examples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightedAnswer {
+    pub part: u8,
+    pub answer: String,
+}
+
+// The description of a puzzle as shown on its page. Part 2 is only shown once part 1 is solved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PuzzleDescription {
+    // The HTML of each part that is available
+    parts: Vec<String>,
+}
+
+impl PuzzleDescription {
+    // Pulls the description out of the HTML of a puzzle page
+    pub fn parse(html: &str) -> Result<PuzzleDescription, String> {
+        lazy_static! {
+            static ref RE: Regex =
+                Regex::new(r#"(?s)<article class="day-desc">(.*?)</article>"#).unwrap();
+        }
+        let parts = RE.captures_iter(html)
+            .map(|captures| captures[1].to_string())
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            Err("The page does not contain a puzzle description".to_string())
+        } else {
+            Ok(PuzzleDescription { parts })
+        }
+    }
+
+    // Whether both parts of the puzzle are described
+    pub fn is_complete(&self) -> bool {
+        self.parts.len() >= 2
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.render(true)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.render(false)
+    }
+
+    // Returns the contents of the example blocks in the order they appear
+    pub fn examples(&self) -> Vec<Example> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+        }
+        self.parts.iter()
+            .zip(1..)
+            .flat_map(|(html, part)| RE.captures_iter(html)
+                .map(move |captures| Example { part, input: strip_tags(&captures[1]) }))
+            .collect()
+    }
+
+    // Returns the highlighted values (e.g. <code><em>514579</em></code>) in the order they appear
+    pub fn highlighted_answers(&self) -> Vec<HighlightedAnswer> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(
+                r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+        }
+        self.parts.iter()
+            .zip(1..)
+            .flat_map(|(html, part)| RE.captures_iter(html)
+                .map(move |captures| {
+                    let answer = captures.get(1).or_else(|| captures.get(2)).unwrap();
+                    HighlightedAnswer { part, answer: strip_tags(answer.as_str()) }
+                }))
+            .collect()
+    }
+
+    fn render(&self, markdown: bool) -> String {
+        let rendered = self.parts.iter()
+            .map(|html| render_part(html, markdown))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        rendered.trim().to_string() + "\n"
+    }
+}
+
+// Converts the HTML of a part to Markdown or to plain text. Example blocks are kept as they are
+// and everything else is reflowed into paragraphs.
+fn render_part(html: &str, markdown: bool) -> String {
+    lazy_static! {
+        static ref PRE_RE: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+        static ref BLANK_LINES_RE: Regex = Regex::new(r"\n{3,}").unwrap();
+    }
+    let mut output = String::new();
+    let mut last = 0;
+    for captures in PRE_RE.captures_iter(html) {
+        let block = captures.get(0).unwrap();
+        output.push_str(&render_inline(&html[last..block.start()], markdown));
+        let code = strip_tags(&captures[1]);
+        if markdown {
+            output.push_str(&format!("\n\n```\n{}\n```\n\n", code.trim_end()));
+        } else {
+            let indented = code.trim_end().lines()
+                .map(|line| format!("    {}", line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            output.push_str(&format!("\n\n{}\n\n", indented));
+        }
+        last = block.end();
+    }
+    output.push_str(&render_inline(&html[last..], markdown));
+    BLANK_LINES_RE.replace_all(output.trim(), "\n\n").to_string()
+}
+
+fn render_inline(html: &str, markdown: bool) -> String {
+    lazy_static! {
+        static ref HEADING_RE: Regex = Regex::new(r"(?s)<h2[^>]*>(.*?)</h2>").unwrap();
+        static ref HIGHLIGHT_RE: Regex = Regex::new(
+            r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+        static ref CODE_RE: Regex = Regex::new(r"(?s)<code>(.*?)</code>").unwrap();
+        static ref EM_RE: Regex = Regex::new(r"(?s)<em[^>]*>(.*?)</em>").unwrap();
+        static ref LINK_RE: Regex = Regex::new(r#"(?s)<a [^>]*href="([^"]*)"[^>]*>(.*?)</a>"#)
+            .unwrap();
+        static ref ITEM_RE: Regex = Regex::new(r"<li>").unwrap();
+        static ref BLOCK_RE: Regex = Regex::new(r"</?(p|ul|ol)>").unwrap();
+    }
+    let html = html.replace('\n', " ");
+    let html = HEADING_RE.replace_all(&html, |c: &Captures| {
+        let prefix = if markdown { "## " } else { "" };
+        format!("\n\n{}{}\n\n", prefix, &c[1])
+    });
+    let html = HIGHLIGHT_RE.replace_all(&html, |c: &Captures| {
+        let text = c.get(1).or_else(|| c.get(2)).unwrap().as_str();
+        if markdown { format!("**`{}`**", text) } else { text.to_string() }
+    });
+    let html = CODE_RE.replace_all(&html, |c: &Captures| {
+        if markdown { format!("`{}`", &c[1]) } else { c[1].to_string() }
+    });
+    let html = EM_RE.replace_all(&html, |c: &Captures| {
+        if markdown { format!("*{}*", &c[1]) } else { c[1].to_string() }
+    });
+    let html = LINK_RE.replace_all(&html, |c: &Captures| {
+        if markdown { format!("[{}]({})", &c[2], &c[1]) } else { c[2].to_string() }
+    });
+    let html = ITEM_RE.replace_all(&html, "\n- ");
+    let html = BLOCK_RE.replace_all(&html, "\n\n");
+    strip_tags(&html)
+        .lines()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Removes any remaining tags and decodes the entities used on the site
+fn strip_tags(html: &str) -> String {
+    lazy_static! {
+        static ref TAG_RE: Regex = Regex::new(r"<[^>]*>").unwrap();
+    }
+    TAG_RE.replace_all(html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle_description::{Example, HighlightedAnswer, PuzzleDescription};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en-us"><head><title>Day 1 - Advent of Code 2020</title></head><body>
+<main>
+<article class="day-desc"><h2>--- Day 1: Report Repair ---</h2><p>Before you leave, the Elves in accounting just need you to fix your <em>expense report</em> (your puzzle input); apparently, something isn't quite adding up.</p>
+<p>Specifically, they need you to <em>find the two entries that sum to <code>2020</code></em> and then multiply those two numbers together.</p>
+<p>For example, suppose your expense report contained the following:</p>
+<pre><code>1721
+979
+366
+299
+675
+1456
+</code></pre>
+<p>In this list, the two entries that sum to <code>2020</code> are <code>1721</code> and <code>299</code>. Multiplying them together produces <code>1721 * 299 = 514579</code>, so the correct answer is <code><em>514579</em></code>.</p>
+<p>Of course, your expense report is much larger. <em>Find the two entries that sum to <code>2020</code>; what do you get if you multiply them together?</em></p>
+</article>
+<p>Your puzzle answer was <code>440979</code>.</p><article class="day-desc"><h2 id="part2">--- Part Two ---</h2><p>The Elves in accounting are thankful for your help; one of them even offers you a starfish coin they had left over from a past vacation. They offer you a second one if you can find <em>three</em> numbers in your expense report that meet the same criteria.</p>
+<p>Using the above example again, the three entries that sum to <code>2020</code> are <code>979</code>, <code>366</code>, and <code>675</code>. Multiplying them together produces the answer, <code><em>241861950</em></code>.</p>
+<ul>
+<li>A <a href="/2020/about">list</a> with <code>a &lt; b &amp;&amp; c</code></li>
+</ul>
+</article>
+</main></body></html>"#;
+
+    #[test]
+    fn test_parse() {
+        let description = PuzzleDescription::parse(PAGE).unwrap();
+        assert!(description.is_complete());
+        let part_1_only = PAGE.find("<p>Your puzzle answer").unwrap();
+        let description = PuzzleDescription::parse(&PAGE[..part_1_only]).unwrap();
+        assert!(!description.is_complete());
+        assert!(PuzzleDescription::parse("<html><body>404 Not Found</body></html>").is_err());
+    }
+
+    #[test]
+    fn test_examples_and_highlighted_answers() {
+        let description = PuzzleDescription::parse(PAGE).unwrap();
+        assert_eq!(description.examples(), vec![
+            Example { part: 1, input: "1721\n979\n366\n299\n675\n1456\n".to_string() },
+        ]);
+        assert_eq!(description.highlighted_answers(), vec![
+            HighlightedAnswer { part: 1, answer: "514579".to_string() },
+            HighlightedAnswer { part: 2, answer: "241861950".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = PuzzleDescription::parse(PAGE).unwrap().to_markdown();
+        let lines = markdown.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "## --- Day 1: Report Repair ---");
+        assert_eq!(lines[1], "");
+        assert!(lines[2].starts_with("Before you leave, the Elves in accounting just need you \
+                                      to fix your *expense report* (your puzzle input);"));
+        assert!(markdown.contains("*find the two entries that sum to `2020`*"));
+        assert!(markdown.contains("\n\n```\n1721\n979\n366\n299\n675\n1456\n```\n\n"));
+        assert!(markdown.contains("so the correct answer is **`514579`**."));
+        assert!(markdown.contains("\n\n## --- Part Two ---\n\n"));
+        assert!(markdown.contains("\n- A [list](/2020/about) with `a < b && c`"));
+        assert!(!markdown.contains("Your puzzle answer"));
+        assert!(markdown.ends_with("`a < b && c`\n"));
+    }
+
+    #[test]
+    fn test_to_text() {
+        let text = PuzzleDescription::parse(PAGE).unwrap().to_text();
+        assert!(text.starts_with("--- Day 1: Report Repair ---\n\nBefore you leave"));
+        assert!(text.contains("fix your expense report (your puzzle input);"));
+        assert!(text.contains("\n\n    1721\n    979\n    366\n    299\n    675\n    1456\n\n"));
+        assert!(text.contains("so the correct answer is 514579."));
+        assert!(text.contains("\n- A list with a < b && c"));
+        assert!(!text.contains("<em>"));
+        assert!(!text.contains("&lt;"));
+    }
+}
@@ -1,164 +1,879 @@
+use crate::answer::Answer;
+use crate::clock::{self, Clock, SystemClock};
+use crate::fetch_error::FetchError;
+use crate::input_metadata::{InputMetadata, InputStatus};
+use crate::leaderboard::Leaderboard;
+use crate::logger::{Logger, StderrLogger};
+use crate::puzzle_description::PuzzleDescription;
+use crate::session_token::{self, FileProvider, SessionToken, SessionTokenProvider, TokenSource};
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::ops::Not;
+use std::io::{self, Write};
 use std::path::{PathBuf, Path};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
+use regex::Regex;
 use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+
+// Puzzle input used to be stored directly under the input path as puzzle/<day>. Inputs found in
+// that layout belong to this year and are moved to puzzle/<year>/<day> when first requested.
+const LEGACY_YEAR: u16 = 2020;
+
+// Identifies this tool (and how to get in touch about it) to the site, as its maintainer asks
+const USER_AGENT: &str = concat!("aoc2020/", env!("CARGO_PKG_VERSION"),
+                                 " (+https://github.com/akaritakai/AdventOfCode2020-Rust; ",
+                                 "akaritakai@gmail.com)");
+
+// How long a downloaded private leaderboard is used before it is downloaded again. The site asks
+// for private leaderboards not to be requested more often than this.
+const LEADERBOARD_REFRESH: Duration = Duration::from_secs(15 * 60);
+
+// Where a puzzle input was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputOrigin {
+    // The in-memory cache of this fetcher
+    Memory,
+
+    // The local store on disk
+    Local,
+
+    // The Advent of Code site
+    Remote,
+
+    // A file given by the user
+    File,
+
+    // Standard input
+    Stdin,
+}
+
+impl fmt::Display for InputOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputOrigin::Memory => write!(f, "memory"),
+            InputOrigin::Local => write!(f, "local"),
+            InputOrigin::Remote => write!(f, "remote"),
+            InputOrigin::File => write!(f, "file"),
+            InputOrigin::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+// What the site made of a submitted answer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubmissionOutcome {
+    Correct,
+    TooHigh,
+    TooLow,
+
+    // The answer is wrong, but the site didn't say in which direction
+    Wrong,
+
+    // An answer was submitted too recently. Nothing was checked and we have to wait before trying
+    // again.
+    RateLimited { wait: Duration },
+
+    // The part has already been solved (or part 1 hasn't been solved yet when submitting part 2)
+    AlreadySolved,
+}
+
+impl fmt::Display for SubmissionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubmissionOutcome::Correct => write!(f, "That's the right answer!"),
+            SubmissionOutcome::TooHigh => write!(f, "That's not the right answer: it is too high"),
+            SubmissionOutcome::TooLow => write!(f, "That's not the right answer: it is too low"),
+            SubmissionOutcome::Wrong => write!(f, "That's not the right answer"),
+            SubmissionOutcome::RateLimited { wait } => {
+                write!(f, "An answer was submitted too recently. Try again in {}s", wait.as_secs())
+            },
+            SubmissionOutcome::AlreadySolved => {
+                write!(f, "This part is already solved (or is not unlocked yet)")
+            },
+        }
+    }
+}
+
+// The account a session token belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    // The name shown on the site, e.g. '(anonymous user #123456)' for users that haven't set one
+    pub name: String,
+
+    pub token_source: TokenSource,
+}
+
+// When the fetcher may go to the site for something that it keeps in the local store
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchPolicy {
+    // Never go to the site: anything that isn't in the local store is an error
+    OfflineOnly,
+
+    // Use the local store, going to the site only for what isn't there (or can't be trusted)
+    CacheThenRemote,
+
+    // Download puzzle input and descriptions again even if they are in the local store. Each is
+    // still only downloaded once per fetcher, and private leaderboards are still only downloaded
+    // once they are 15 minutes old, as the site asks.
+    RemoteRefresh,
+}
+
+// Deriving Default for an enum needs a newer Rust than the one this project is built with
+#[allow(clippy::derivable_impls)]
+impl Default for FetchPolicy {
+    fn default() -> FetchPolicy {
+        FetchPolicy::CacheThenRemote
+    }
+}
+
+// How the fetcher paces its requests to the site
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestPolicy {
+    // The minimum time between the start of one request and the start of the next
+    pub min_interval: Duration,
+
+    // How many times to retry a request that failed with a server error (5xx)
+    pub max_retries: u32,
+
+    // How long to wait before the first retry. The wait doubles with each retry after that.
+    pub initial_backoff: Duration,
+
+    // The longest to wait before any one retry
+    pub max_backoff: Duration,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> RequestPolicy {
+        RequestPolicy {
+            min_interval: Duration::from_secs(1),
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RequestPolicy {
+    // How long to wait before the given retry (counting from 0): the initial backoff doubled for
+    // each earlier retry, but never longer than the maximum
+    fn backoff(&self, retry: u32) -> Duration {
+        2u32.checked_pow(retry)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
 
 pub struct PuzzleInputFetcher {
     // The base URL for Advent of Code (by default 'https://adventofcode.com/')
     base_url: String,
 
-    // The location where puzzle input is stored (by default 'puzzle')
+    // The location where puzzle input is stored (by default 'puzzle'). Each year has its own
+    // directory within it, e.g. 'puzzle/2020/1' for the input of day 1 in 2020.
     input_path: PathBuf,
 
-    // The location where the session token is stored (by default 'cookie.txt')
-    session_token_path: PathBuf,
+    // Where to look for the session token, in order (see session_token::default_providers)
+    session_token_providers: Vec<Box<dyn SessionTokenProvider>>,
+
+    // The input cache that stores our puzzles keyed by (year, day). Each day has its own lock which
+    // is held while the input is being loaded, so concurrent requests for the same day only load
+    // it once while requests for other days can proceed. The outer lock is only held long enough
+    // to find (or add) the entry for a day.
+    inputs: Mutex<HashMap<(u16, u8), CachedInput>>,
+
+    // The session token cache. The lock is held while the token is being loaded.
+    session_token: Mutex<Option<SessionToken>>,
+
+    // Whether the site accepts the session token, which is checked before the first download. If
+    // it does, this holds the account the token belongs to (if the site could be asked), and if it
+    // doesn't, where the token came from. The lock is held while checking.
+    session_check: Mutex<Option<std::result::Result<Option<Account>, TokenSource>>>,
+
+    // Used to tell whether a puzzle has unlocked yet and to pace requests
+    clock: Arc<dyn Clock>,
 
-    // Per-day lock for loading the puzzle input into our input cache
-    is_input_set: Vec<Arc<RwLock<bool>>>,
+    // The client used for every request to the site
+    client: Client,
 
-    // The input cache that stores our puzzles
-    inputs: Vec<String>,
+    request_policy: RequestPolicy,
 
-    // Lock for loading the session token into our session token cache
-    is_session_token_set: Arc<RwLock<bool>>,
+    fetch_policy: FetchPolicy,
 
-    // The session token cache
-    session_token: String,
+    // Where failures to save to the local store are reported
+    logger: Arc<dyn Logger>,
+
+    // When the last request to the site was started. The lock is held while waiting to start the
+    // next request so that requests from several threads are spaced out too.
+    last_request: Mutex<Option<SystemTime>>,
 }
 
 impl PuzzleInputFetcher {
     // Creates a PuzzleInputFetcher using the default values
     pub fn create() -> PuzzleInputFetcher {
+        PuzzleInputFetcher::create_with_session_token(None)
+    }
+
+    // Creates a PuzzleInputFetcher using the default values, except that the given session token
+    // (if any) is used ahead of any other
+    pub fn create_with_session_token(session_token: Option<&str>) -> PuzzleInputFetcher {
+        let providers = session_token::default_providers(session_token, Path::new("cookie.txt"));
         PuzzleInputFetcher::create_custom(
             "https://adventofcode.com",
             Path::new("puzzle"),
             Path::new("cookie.txt"))
+            .with_session_token_providers(providers)
     }
 
     // Creates a PuzzleInputFetcher using the with a specified base url, puzzle input path, and
-    // session token path. Used only for testing.
+    // session token path (the only place the session token is looked for)
     pub fn create_custom(base_url: &str, input_path: &Path, session_token_path: &Path)
         -> PuzzleInputFetcher {
-        let mut is_input_set = Vec::with_capacity(25);
-        (0..25).for_each(|_| is_input_set.push(Arc::new(RwLock::new(false))));
+        PuzzleInputFetcher::create_with_clock(base_url, input_path, session_token_path,
+                                              Arc::new(SystemClock))
+    }
+
+    // Same as create_custom, but with a clock other than the system clock. Used only for testing.
+    pub fn create_with_clock(base_url: &str, input_path: &Path, session_token_path: &Path,
+                             clock: Arc<dyn Clock>) -> PuzzleInputFetcher {
         PuzzleInputFetcher {
             base_url: base_url.to_string(),
             input_path: input_path.to_path_buf(),
-            is_input_set,
-            inputs: vec![String::new(); 25],
-            session_token_path: session_token_path.to_path_buf(),
-            is_session_token_set: Arc::new(RwLock::new(false)),
-            session_token: String::new(),
+            inputs: Mutex::new(HashMap::new()),
+            session_token_providers: vec![Box::new(FileProvider::new(session_token_path))],
+            session_token: Mutex::new(None),
+            session_check: Mutex::new(None),
+            clock,
+            client: Client::builder()
+                .user_agent(USER_AGENT)
+                .build()
+                .unwrap(),
+            request_policy: RequestPolicy::default(),
+            fetch_policy: FetchPolicy::default(),
+            last_request: Mutex::new(None),
+            logger: Arc::new(StderrLogger),
+        }
+    }
+
+    // Changes where the session token is looked for
+    pub fn with_session_token_providers(mut self,
+                                        providers: Vec<Box<dyn SessionTokenProvider>>)
+        -> PuzzleInputFetcher {
+        self.session_token_providers = providers;
+        self
+    }
+
+    // Changes how requests to the site are paced
+    pub fn with_request_policy(mut self, request_policy: RequestPolicy) -> PuzzleInputFetcher {
+        self.request_policy = request_policy;
+        self
+    }
+
+    // Changes when the site is used for what is (or could be) in the local store
+    pub fn with_fetch_policy(mut self, fetch_policy: FetchPolicy) -> PuzzleInputFetcher {
+        self.fetch_policy = fetch_policy;
+        self
+    }
+
+    // Changes where failures to save to the local store are reported
+    pub fn with_logger(mut self, logger: Arc<dyn Logger>) -> PuzzleInputFetcher {
+        self.logger = logger;
+        self
+    }
+
+    // The base URL for Advent of Code that requests are sent to
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // The location where puzzle input is stored
+    pub fn input_path(&self) -> &Path {
+        &self.input_path
+    }
+
+    // Sleeps until the puzzle for the given day unlocks, returning how long that took
+    pub fn wait_for_unlock(&self, year: u16, day: u8) -> Duration {
+        let mut waited = Duration::from_secs(0);
+        while let Some(remaining) = clock::time_until_unlock(self.clock.as_ref(), year, day) {
+            self.clock.sleep(remaining);
+            waited += remaining;
         }
+        waited
+    }
+
+    // Returns the puzzle input for the given year and day first by fetching it from the in-memory
+    // cache, then by fetching it from the local store, and finally by fetching it from the remote
+    // store (the site itself).
+    pub fn get_puzzle_input(&self, year: u16, day: u8) -> Result<String> {
+        self.get_puzzle_input_with_origin(year, day).map(|(input, _)| input)
     }
 
-    // Returns the puzzle input for the given day first by fetching it from the in-memory cache,
-    // then by fetching it from the local store, and finally by fetching it from the remote store
-    // (the site itself).
-    pub fn get_puzzle_input(&mut self, day: u8) -> Result<&str> {
-        let index = (day - 1) as usize;
-        if self.is_input_set[index].read().unwrap().not() {
+    // Same as get_puzzle_input, but also reports where the puzzle input was found
+    pub fn get_puzzle_input_with_origin(&self, year: u16, day: u8)
+        -> Result<(String, InputOrigin)> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        let found = self.get_cached_puzzle_input(year, day, || {
             // Puzzle is not in our cache
-            let mut is_input_set = self.is_input_set[index].write().unwrap();
-            if is_input_set.not() {
-                if let Ok(local_input) = self.fetch_local_puzzle_input(day) {
-                    // Puzzle is in our local store
-                    self.inputs[index].push_str(local_input.as_str());
-                    *is_input_set = true;
-                    return Ok(self.inputs[index].as_str())
-                }
-                // Puzzle is not in our local store
-                if self.is_session_token_set.read().unwrap().not() {
-                    // Session token is not cached
-                    let mut is_session_token_set = self.is_session_token_set.write().unwrap();
-                    if is_session_token_set.not() {
-                        let session_token = self.fetch_session_token()?;
-                        self.session_token.push_str(session_token.trim());
-                        *is_session_token_set = true;
-                    }
+            if let Some(local_input) = self.find_stored_puzzle_input(year, day)? {
+                // Puzzle is in our local store
+                return Ok(Some((local_input, InputOrigin::Local)));
+            }
+            // Puzzle is not in our local store (or what is there can't be trusted)
+            let remote_input = self.download_puzzle_input(year, day)?;
+            self.store_puzzle_input(year, day, &remote_input);
+            Ok(Some((remote_input, InputOrigin::Remote)))
+        })?;
+        Ok(found.unwrap())
+    }
+
+    // Returns the puzzle input for the given year and day from the in-memory cache, or else loads
+    // it with the given function and keeps whatever that finds in the cache. Each day has its own
+    // lock which is held while loading, so concurrent requests for the same day only load it once
+    // (however many sources the loading goes through).
+    pub fn get_cached_puzzle_input<F>(&self, year: u16, day: u8, load: F)
+        -> Result<Option<(String, InputOrigin)>>
+        where F: FnOnce() -> Result<Option<(String, InputOrigin)>> {
+        let cache_entry = self.inputs.lock().unwrap()
+            .entry((year, day))
+            .or_default()
+            .clone();
+        let mut cached_input = cache_entry.lock().unwrap();
+        if let Some(input) = cached_input.as_ref() {
+            return Ok(Some((input.clone(), InputOrigin::Memory)));
+        }
+        let found = load()?;
+        if let Some((input, _)) = found.as_ref() {
+            *cached_input = Some(input.clone());
+        }
+        Ok(found)
+    }
+
+    // Returns the puzzle input in the local store, or None if it should be downloaded instead:
+    // because it isn't there, because it can't be trusted or because the fetch policy is to
+    // refresh it. When offline that is an error, which says where the input was expected or what
+    // is wrong with what is there.
+    pub fn find_stored_puzzle_input(&self, year: u16, day: u8) -> Result<Option<String>> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        if self.fetch_policy == FetchPolicy::RemoteRefresh {
+            return Ok(None);
+        }
+        match self.fetch_local_puzzle_input(year, day) {
+            Ok(local_input) => Ok(Some(local_input)),
+            Err(FetchError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.check_online(format!("The puzzle input for {} day {}", year, day),
+                                  Some(self.local_path(year, day)))?;
+                Ok(None)
+            },
+            Err(e) => {
+                self.check_online(format!("The puzzle input for {} day {}", year, day), None)
+                    .map_err(|_| e)?;
+                Ok(None)
+            },
+        }
+    }
+
+    // Checks the puzzle input in the local store against the metadata recorded when it was
+    // downloaded
+    pub fn verify_puzzle_input(&self, year: u16, day: u8) -> InputStatus {
+        if year == LEGACY_YEAR {
+            self.migrate_legacy_puzzle_input(day);
+        }
+        match fs::read_to_string(self.local_path(year, day)) {
+            Ok(input) => InputStatus::check(&input, self.local_metadata(year, day).as_ref()),
+            Err(_) => InputStatus::Missing,
+        }
+    }
+
+    // Downloads the puzzle input again, replacing whatever is in the local store and the cache
+    pub fn refetch_puzzle_input(&self, year: u16, day: u8) -> Result<String> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        let cache_entry = self.inputs.lock().unwrap()
+            .entry((year, day))
+            .or_default()
+            .clone();
+        let mut cached_input = cache_entry.lock().unwrap();
+        let remote_input = self.download_puzzle_input(year, day)?;
+        self.store_puzzle_input(year, day, &remote_input);
+        *cached_input = Some(remote_input.clone());
+        Ok(remote_input)
+    }
+
+    // Returns the description of the puzzle for the given year and day. The page is kept next to
+    // the puzzle input, but is downloaded again while it only describes part 1 since part 2 is
+    // only shown once part 1 is solved. The session token is sent if there is one so that part 2
+    // can be included.
+    pub fn get_puzzle_description(&self, year: u16, day: u8) -> Result<PuzzleDescription> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        let path = self.local_path(year, day).with_extension("html");
+        let local_description = fs::read_to_string(&path).ok()
+            .and_then(|html| PuzzleDescription::parse(&html).ok());
+        if let Some(description) = local_description.as_ref() {
+            if description.is_complete() && self.fetch_policy != FetchPolicy::RemoteRefresh {
+                return Ok(description.clone());
+            }
+        }
+        let remote_description = self
+            .check_online(format!("The puzzle description for {} day {}", year, day),
+                          Some(path.clone()))
+            .and_then(|_| self.check_unlocked(year, day))
+            .and_then(|_| self.fetch_remote_puzzle_page(year, day))
+            .and_then(|html| PuzzleDescription::parse(&html).map(|description| (html, description))
+                .map_err(|e| FetchError::UnexpectedResponse(format!(
+                    "Failed to fetch puzzle description for {} day {}: {}", year, day, e))));
+        match (remote_description, local_description) {
+            (Ok((html, description)), _) => {
+                // As with puzzle input, failing to save the page is not a critical error
+                if let Err(e) = write_atomically(&path, &html) {
+                    self.logger.warn(&format!("Failed to save puzzle description for {} day {} \
+                                               to {}: {}", year, day, path.display(), e));
                 }
-                let session_token = self.session_token.as_str();
-                let remote_input = self.fetch_remote_puzzle_input(day, session_token)?;
-                self.store_puzzle_input_locally(day, remote_input.as_str());
-                self.inputs[index].push_str(remote_input.as_str());
-                *is_input_set = true;
-                return Ok(self.inputs[index].as_str())
+                Ok(description)
+            },
+            // Part 1 on its own is better than nothing
+            (Err(_), Some(description)) => Ok(description),
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    // Returns the private leaderboard with the given id for the given year. The leaderboard is kept
+    // in the local store (e.g. 'puzzle/2020/leaderboard/123.json') and only downloaded again once
+    // it is older than the site's minimum refresh time.
+    pub fn get_private_leaderboard(&self, year: u16, id: u64) -> Result<Leaderboard> {
+        if year < 2015 {
+            return Err(FetchError::InvalidRequest(format!("There is no event for {}", year)));
+        }
+        let path = self.input_path.join(year.to_string())
+            .join("leaderboard")
+            .join(format!("{}.json", id));
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| self.clock.now().duration_since(modified).unwrap_or_default());
+        if let Ok(age) = age {
+            let local_leaderboard = fs::read_to_string(&path).ok()
+                .and_then(|json| Leaderboard::parse(&json).ok());
+            // Offline, a stale leaderboard is better than nothing
+            let fresh = age < LEADERBOARD_REFRESH || self.fetch_policy == FetchPolicy::OfflineOnly;
+            if let Some(leaderboard) = local_leaderboard.filter(|_| fresh) {
+                return Ok(leaderboard);
             }
         }
-        Ok(self.inputs[index].as_str())
+        self.check_online(format!("Private leaderboard {} for {}", id, year), Some(path.clone()))?;
+        let session_token = self.get_session_token_with_source()?;
+        let url = format!("{}{}", self.base_url, leaderboard_url_path(year, id));
+        let response = self.send(|| self.client.get(&url)
+            .header("Cookie", format!("session={}", session_token.token)));
+        let context = format!("Failed to fetch private leaderboard {} for {}", id, year);
+        let json = response_text(&context, response, Some(&session_token.source))?;
+        // The site sends back a page rather than JSON to those who can't see the leaderboard
+        let leaderboard = Leaderboard::parse(&json)
+            .map_err(|e| FetchError::UnexpectedResponse(format!(
+                "{}: {}. Check that the id is right and that the account is a member of the \
+                 leaderboard.", context, e)))?;
+        if let Err(e) = write_atomically(&path, &json) {
+            self.logger.warn(&format!("Failed to save private leaderboard {} for {} to {}: {}",
+                                      id, year, path.display(), e));
+        }
+        Ok(leaderboard)
+    }
+
+    // Submits the answer for the given part of a puzzle and reports what the site made of it
+    pub fn submit(&self, year: u16, day: u8, part: u8, answer: &Answer)
+        -> Result<SubmissionOutcome> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        if !(1..=2).contains(&part) {
+            return Err(FetchError::InvalidRequest(
+                format!("There is no part {} for {} day {}", part, year, day)));
+        }
+        if let Answer::Grid(_) = answer {
+            return Err(FetchError::InvalidRequest(
+                "Answers drawn on a grid can't be submitted. \
+                 Read the letters off the grid and submit those instead.".to_string()));
+        }
+        self.check_online(format!("Submitting an answer for {} day {}", year, day), None)?;
+        self.check_unlocked(year, day)?;
+        let session_token = self.get_session_token_with_source()?;
+        let path = format!("{}{}", self.base_url, submit_url_path(year, day));
+        let form = [("level", part.to_string()), ("answer", answer.to_string())];
+        let response = self.send(|| self.client.post(&path)
+            .header("Cookie", format!("session={}", session_token.token))
+            .form(&form));
+        let context = format!("Failed to submit answer for {} day {}", year, day);
+        let body = response_text(&context, response, Some(&session_token.source))?;
+        parse_submission_response(&body)
+    }
+
+    // Fails if the fetch policy doesn't allow going to the site. What needs the site (and where it
+    // would have been in the local store) is named in the error.
+    fn check_online(&self, what: String, path: Option<PathBuf>) -> Result<()> {
+        match self.fetch_policy {
+            FetchPolicy::OfflineOnly => Err(FetchError::Offline { what, path }),
+            _ => Ok(()),
+        }
+    }
+
+    // Fails if the puzzle for the given day hasn't unlocked yet, as there is no point in asking the
+    // site for it
+    fn check_unlocked(&self, year: u16, day: u8) -> Result<()> {
+        match clock::time_until_unlock(self.clock.as_ref(), year, day) {
+            Some(remaining) => Err(FetchError::NotYetUnlocked { year, day, remaining }),
+            None => Ok(()),
+        }
+    }
+
+    // Returns the session token (and where it came from) from the cache, loading it into the
+    // cache if needed
+    pub fn get_session_token_with_source(&self) -> Result<SessionToken> {
+        let mut cached_session_token = self.session_token.lock().unwrap();
+        if let Some(session_token) = cached_session_token.as_ref() {
+            return Ok(session_token.clone());
+        }
+        let session_token = session_token::find_session_token(&self.session_token_providers)?;
+        *cached_session_token = Some(session_token.clone());
+        Ok(session_token)
+    }
+
+    // Asks the site who the session token belongs to, which confirms that the token works
+    pub fn check_session(&self) -> Result<Account> {
+        self.check_online("Checking the session token".to_string(), None)?;
+        let session_token = self.get_session_token_with_source()?;
+        self.fetch_account(&session_token)?
+            .ok_or_else(|| FetchError::Unauthorized {
+                context: "Failed to check session token".to_string(),
+                token_source: session_token.source,
+            })
+    }
+
+    // Makes sure the site accepts the session token before anything is downloaded with it, so that
+    // an expired token is reported as such rather than as a failed download. This is only checked
+    // once, and only a clear rejection stops the download: if the check itself fails, the download
+    // goes ahead and reports any problem.
+    fn check_session_before_download(&self, year: u16, day: u8) -> Result<Option<Account>> {
+        let mut session_check = self.session_check.lock().unwrap();
+        if session_check.is_none() {
+            let session_token = self.get_session_token_with_source()?;
+            *session_check = Some(match self.fetch_account(&session_token) {
+                Ok(Some(account)) => Ok(Some(account)),
+                Ok(None) => Err(session_token.source),
+                Err(_) => Ok(None),
+            });
+        }
+        session_check.clone().unwrap()
+            .map_err(|token_source| FetchError::Unauthorized {
+                context: format!("Not downloading puzzle input for {} day {}", year, day),
+                token_source,
+            })
+    }
+
+    // Returns the account the site shows as logged in (or None if it shows nobody logged in)
+    fn fetch_account(&self, session_token: &SessionToken) -> Result<Option<Account>> {
+        let path = format!("{}{}", self.base_url, account_url_path());
+        let response = self.send(|| self.client.get(&path)
+            .header("Cookie", format!("session={}", session_token.token)));
+        let body = response_text("Failed to check session token", response,
+                                 Some(&session_token.source))?;
+        Ok(parse_account_name(&body).map(|name| Account {
+            name,
+            token_source: session_token.source.clone(),
+        }))
+    }
+
+    // Returns the puzzle input in the local store, unless it is corrupt or isn't puzzle input
+    fn fetch_local_puzzle_input(&self, year: u16, day: u8) -> Result<String> {
+        if year == LEGACY_YEAR {
+            self.migrate_legacy_puzzle_input(day);
+        }
+        let input = fs::read_to_string(self.local_path(year, day))
+            .map_err(|e| FetchError::Io {
+                context: format!("Failed to fetch local puzzle for {} day {}", year, day),
+                source: e,
+            })?;
+        let status = InputStatus::check(&input, self.local_metadata(year, day).as_ref());
+        if status.is_bad() {
+            let path = self.local_path(year, day);
+            Err(FetchError::CorruptLocalInput { year, day, path, status })
+        } else {
+            Ok(input)
+        }
+    }
+
+    // Returns the metadata stored next to the puzzle input (if there is any that can be read)
+    fn local_metadata(&self, year: u16, day: u8) -> Option<InputMetadata> {
+        fs::read_to_string(self.metadata_path(year, day)).ok()
+            .and_then(|text| InputMetadata::parse(&text).ok())
+    }
+
+    // Downloads the puzzle input from the site. Nothing is kept in the local store (or the cache):
+    // use store_puzzle_input for that.
+    pub fn download_puzzle_input(&self, year: u16, day: u8) -> Result<String> {
+        if year < 2015 || !(1..=25).contains(&day) {
+            return Err(no_puzzle_error(year, day));
+        }
+        self.check_online(format!("Downloading the puzzle input for {} day {}", year, day), None)?;
+        self.check_unlocked(year, day)?;
+        let session_token = self.get_session_token_with_source()?;
+        self.check_session_before_download(year, day)?;
+        self.fetch_remote_puzzle_input(year, day, &session_token)
     }
 
-    fn fetch_local_puzzle_input(&self, day: u8) -> Result<String> {
-        let path = self.input_path.to_path_buf().join(day.to_string());
-        fs::read_to_string(path)
-            .map_err(|e| format!("Failed to fetch local puzzle for day {}: {}", day, e))
+    // Moves puzzle input stored in the old puzzle/<day> layout to puzzle/<year>/<day>, unless the
+    // input is already in the new layout
+    fn migrate_legacy_puzzle_input(&self, day: u8) {
+        let legacy_path = self.input_path.join(day.to_string());
+        let path = self.local_path(LEGACY_YEAR, day);
+        if legacy_path.is_file() && !path.exists() {
+            // If the move fails the input is fetched again, which is no worse than not migrating
+            let result = fs::create_dir_all(path.parent().unwrap())
+                .and_then(|_| fs::rename(&legacy_path, &path));
+            if let Err(e) = result {
+                self.logger.warn(&format!("Failed to move puzzle input from {} to {}: {}",
+                                          legacy_path.display(), path.display(), e));
+            }
+        }
     }
 
-    fn store_puzzle_input_locally(&self, day: u8, input: &str) {
+    // Stores downloaded puzzle input in the local store along with the metadata used to check it
+    // later on (including the account it was downloaded with, if the site said)
+    pub fn store_puzzle_input(&self, year: u16, day: u8, input: &str) {
         // Storing puzzle input locally on disk is a 'nice to have' feature in that it reduces load
         // on the Advent of Code site for subsequent runs, but if we can't save to disk -- it
-        // shouldn't be a critical error.
-        let _ = fs::create_dir_all(self.input_path.to_path_buf());
-        let _ = fs::write(self.input_path.join(day.to_string()), input);
-    }
-
-    fn fetch_remote_puzzle_input(&self, day: u8, session_token: &str) -> Result<String> {
-        let client = reqwest::blocking::Client::new();
-        let path = format!("{}{}", self.base_url, remote_url_path(day));
-        let response = client.get(&path)
-            .header("Cookie", format!("session={}", session_token))
-            .send()
-            .map_err(|e| format!("Failed to fetch remote puzzle input for day {}: {}", day, e))?;
-        if response.status() != StatusCode::OK {
-            Err(format!("Failed to fetch remote puzzle input for day {}: \
-                         Got status code = {}", day, response.status()))
-        } else {
-            response.text()
-                .map_err(|e| format!("Failed to fetch remote puzzle input for day {}: \
-                                            Failed to read body as text: {}", day, e))
-        }
-    }
-
-    // Fetches the session token from the disk
-    fn fetch_session_token(&self) -> Result<String> {
-        let session_token = fs::read_to_string(self.session_token_path.to_path_buf())
-            .map(|s| s.trim().to_string())
-            .map_err(|e| format!("Failed to fetch session token from {}: {}",
-                                 path_to_str(&self.session_token_path), e))?;
-        let has_right_length = session_token.len() == 96;
-        let has_right_charset = session_token.chars()
-            .all(|x| (x >= '0' && x <= '9') || (x >= 'a' && x <= 'z'));
-        if !has_right_length || !has_right_charset {
-            Err(format!("Session token is not in the right format. \
-                         Expected 96 lowercase hex digits. Got: {}", session_token))
-        } else {
-            Ok(session_token)
+        // shouldn't be a critical error. It is reported, though, as the input will be downloaded
+        // again next time.
+        let account = self.session_check.lock().unwrap().clone()
+            .and_then(|session_check| session_check.ok())
+            .flatten();
+        let account_name = account.as_ref().map(|account| account.name.as_str());
+        let path = self.local_path(year, day);
+        let source_url = format!("{}{}", self.base_url, remote_url_path(year, day));
+        let metadata = InputMetadata::describe(input, &source_url, self.clock.now(),
+                                               account_name);
+        let metadata_path = self.metadata_path(year, day);
+        if let Err(e) = write_atomically(&path, input) {
+            self.logger.warn(&format!("Failed to save puzzle input for {} day {} to {}: {}",
+                                      year, day, path.display(), e));
+            return;
+        }
+        if let Err(e) = write_atomically(&metadata_path, &metadata.to_file_contents()) {
+            // The metadata of the input that was replaced would make the new input look corrupt,
+            // whereas input without any metadata is used as it is
+            let _ = fs::remove_file(&metadata_path);
+            self.logger.warn(&format!("Failed to save puzzle input metadata for {} day {} to {}: \
+                                       {}", year, day, metadata_path.display(), e));
+        }
+    }
+
+    fn local_path(&self, year: u16, day: u8) -> PathBuf {
+        self.input_path.join(year.to_string()).join(day.to_string())
+    }
+
+    fn metadata_path(&self, year: u16, day: u8) -> PathBuf {
+        self.local_path(year, day).with_extension("meta")
+    }
+
+    // Sends the request made by the given function, keeping to the request policy. Server errors
+    // are retried with exponential backoff and the last response is returned if they persist.
+    fn send<F: Fn() -> RequestBuilder>(&self, request: F) -> reqwest::Result<Response> {
+        let mut retries = 0;
+        loop {
+            self.wait_for_turn();
+            let response = request().send()?;
+            if !response.status().is_server_error() || retries >= self.request_policy.max_retries {
+                return Ok(response);
+            }
+            self.clock.sleep(self.request_policy.backoff(retries));
+            retries += 1;
+        }
+    }
+
+    // Waits until the minimum time since the last request has passed
+    fn wait_for_turn(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(last_request) = *last_request {
+            let next_request = last_request + self.request_policy.min_interval;
+            if let Ok(remaining) = next_request.duration_since(self.clock.now()) {
+                self.clock.sleep(remaining);
+            }
         }
+        *last_request = Some(self.clock.now());
+    }
+
+    fn fetch_remote_puzzle_page(&self, year: u16, day: u8) -> Result<String> {
+        let path = format!("{}{}", self.base_url, description_url_path(year, day));
+        let session_token = self.get_session_token_with_source().ok();
+        let response = self.send(|| match session_token.as_ref() {
+            Some(session_token) => self.client.get(&path)
+                .header("Cookie", format!("session={}", session_token.token)),
+            None => self.client.get(&path),
+        });
+        // The page can be read without logging in, so an error status says nothing about the token
+        let context = format!("Failed to fetch puzzle description for {} day {}", year, day);
+        response_text(&context, response, None)
+    }
+
+    fn fetch_remote_puzzle_input(&self, year: u16, day: u8, session_token: &SessionToken)
+        -> Result<String> {
+        let path = format!("{}{}", self.base_url, remote_url_path(year, day));
+        let response = self.send(|| self.client.get(&path)
+            .header("Cookie", format!("session={}", session_token.token)));
+        let context = format!("Failed to fetch remote puzzle input for {} day {}", year, day);
+        response_text(&context, response, Some(&session_token.source))
+    }
+}
+
+// Returns the body of a response, failing if the request failed or the response has a status other
+// than 200. For requests that send a session token, the statuses the site uses when it doesn't
+// accept the token are reported as such.
+fn response_text(context: &str, response: reqwest::Result<Response>,
+                 token_source: Option<&TokenSource>) -> Result<String> {
+    let response = response.map_err(|source| FetchError::Transport {
+        context: context.to_string(),
+        source,
+    })?;
+    let status = response.status();
+    match token_source {
+        _ if status == StatusCode::OK => {},
+        Some(token_source) if status == StatusCode::BAD_REQUEST
+            || status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN => {
+            return Err(FetchError::Unauthorized {
+                context: context.to_string(),
+                token_source: token_source.clone(),
+            });
+        },
+        _ => return Err(FetchError::HttpStatus { context: context.to_string(), status }),
+    }
+    response.text().map_err(|source| FetchError::Transport {
+        context: format!("{}: Failed to read body as text", context),
+        source,
+    })
+}
+
+fn no_puzzle_error(year: u16, day: u8) -> FetchError {
+    FetchError::InvalidRequest(format!("There is no puzzle for {} day {}", year, day))
+}
+
+// Writes the contents to a temporary file next to the path and then renames it into place, so that
+// the file at the path is never left partly written. A temporary file left behind by a crash is
+// overwritten the next time.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temp_name = path.file_name().unwrap().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::create_dir_all(path.parent().unwrap())?;
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
     }
+    result
+}
+
+fn remote_url_path(year: u16, day: u8) -> String {
+    format!("/{}/day/{}/input", year, day)
+}
+
+fn description_url_path(year: u16, day: u8) -> String {
+    format!("/{}/day/{}", year, day)
+}
+
+fn submit_url_path(year: u16, day: u8) -> String {
+    format!("/{}/day/{}/answer", year, day)
 }
 
-fn remote_url_path(day: u8) -> String {
-    format!("/2020/day/{}/input", day.to_string())
+fn leaderboard_url_path(year: u16, id: u64) -> String {
+    format!("/{}/leaderboard/private/view/{}.json", year, id)
 }
 
-fn path_to_str(path: &Path) -> String {
-    path.to_path_buf().into_os_string().into_string().unwrap()
+// A page that is only shown to logged in users. Like every page on the site, it shows the name of
+// the user in its header.
+fn account_url_path() -> String {
+    "/settings".to_string()
 }
 
-type Result<T> = std::result::Result<T, String>;
+// Pulls the user name out of the header of a page, e.g. from
+// <div class="user">name <span class="star-count">50*</span></div>
+fn parse_account_name(html: &str) -> Option<String> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r#"<div class="user">([^<]*)"#).unwrap();
+    }
+    RE.captures(html)
+        .map(|captures| captures[1].trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+// Works out the outcome of a submission from the message in the page the site sends back
+fn parse_submission_response(html: &str) -> Result<SubmissionOutcome> {
+    lazy_static! {
+        static ref ARTICLE_RE: Regex = Regex::new(r"(?s)<article[^>]*>(.*?)</article>").unwrap();
+        static ref TAG_RE: Regex = Regex::new(r"<[^>]*>").unwrap();
+        static ref WAIT_RE: Regex =
+            Regex::new(r"You have (?:(\d+)m)? ?(?:(\d+)s)? left to wait").unwrap();
+    }
+    let article = ARTICLE_RE.captures(html)
+        .map_or(html, |captures| captures.get(1).unwrap().as_str());
+    let message = TAG_RE.replace_all(article, "");
+    if message.contains("That's the right answer") {
+        Ok(SubmissionOutcome::Correct)
+    } else if message.contains("your answer is too high") {
+        Ok(SubmissionOutcome::TooHigh)
+    } else if message.contains("your answer is too low") {
+        Ok(SubmissionOutcome::TooLow)
+    } else if message.contains("That's not the right answer") {
+        Ok(SubmissionOutcome::Wrong)
+    } else if message.contains("You don't seem to be solving the right level") {
+        Ok(SubmissionOutcome::AlreadySolved)
+    } else if let Some(captures) = WAIT_RE.captures(&message) {
+        let minutes = captures.get(1).map_or(0, |m| m.as_str().parse::<u64>().unwrap());
+        let seconds = captures.get(2).map_or(0, |s| s.as_str().parse::<u64>().unwrap());
+        Ok(SubmissionOutcome::RateLimited { wait: Duration::from_secs(minutes * 60 + seconds) })
+    } else {
+        Err(FetchError::UnexpectedResponse(
+            format!("Unrecognized response to submission: {}", message.trim())))
+    }
+}
+
+type Result<T> = std::result::Result<T, FetchError>;
+
+// A single day's puzzle input, which is absent until it has been loaded
+type CachedInput = Arc<Mutex<Option<String>>>;
 
 #[cfg(test)]
 mod tests {
-    use crate::puzzle_input_fetcher::{PuzzleInputFetcher, remote_url_path};
+    use crate::answer::Answer;
+    use crate::clock::{unlock_time, Clock, FakeClock};
+    use crate::fetch_error::FetchError;
+    use crate::input_metadata::{sha256, InputMetadata, InputStatus};
+    use crate::logger::Logger;
+    use crate::puzzle_input_fetcher::{Account, FetchPolicy, InputOrigin, PuzzleInputFetcher,
+                                      RequestPolicy,
+                                      SubmissionOutcome, USER_AGENT, account_url_path,
+                                      description_url_path, leaderboard_url_path,
+                                      remote_url_path, submit_url_path, write_atomically};
+    use crate::session_token::TokenSource;
 
     use rand::Rng;
-    use std::fs::File;
+    use std::error::Error;
+    use std::fs;
     use std::io::Write;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
     use tempfile::{tempdir, NamedTempFile};
-    use httpmock::Method::GET;
+    use std::time::{Duration, SystemTime};
+    use httpmock::Method::{GET, POST};
     use httpmock::MockServer;
+    use reqwest::StatusCode;
 
     //noinspection DuplicatedCode
     #[test]
@@ -168,22 +883,23 @@ mod tests {
         let puzzle_store_dir = tempdir().unwrap();
         let session_token_path = NamedTempFile::new().unwrap();
         let session_token = random_session_token();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
         for day in 1..26 {
             let puzzle_input = random_puzzle();
-            let puzzle_file_path = puzzle_store_dir.path().join(day.to_string());
-            let mut puzzle_file = File::create(puzzle_file_path).unwrap();
-            puzzle_file.write_all(puzzle_input.as_bytes()).unwrap();
+            store_puzzle_locally(puzzle_store_dir.path(), 2020, day, &puzzle_input);
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2020, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(501);
             });
-            assert_eq!(fetcher.get_puzzle_input(day).unwrap(), puzzle_input);
+            assert_eq!(fetcher.get_puzzle_input_with_origin(2020, day).unwrap(),
+                       (puzzle_input.clone(), InputOrigin::Local));
+            assert_eq!(fetcher.get_puzzle_input_with_origin(2020, day).unwrap(),
+                       (puzzle_input.clone(), InputOrigin::Memory));
             mock.assert_hits(0);
         }
     }
@@ -197,7 +913,7 @@ mod tests {
         let mut session_token_path = NamedTempFile::new().unwrap();
         let session_token = random_session_token();
         session_token_path.write_all(session_token.as_bytes()).unwrap();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
@@ -205,12 +921,15 @@ mod tests {
             let puzzle_input = random_puzzle();
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2020, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(200)
                     .body(&puzzle_input);
             });
-            assert_eq!(fetcher.get_puzzle_input(day).unwrap(), puzzle_input);
+            assert_eq!(fetcher.get_puzzle_input_with_origin(2020, day).unwrap(),
+                       (puzzle_input.clone(), InputOrigin::Remote));
+            assert_eq!(fetcher.get_puzzle_input_with_origin(2020, day).unwrap(),
+                       (puzzle_input.clone(), InputOrigin::Memory));
             mock.assert();
         }
     }
@@ -224,22 +943,42 @@ mod tests {
         let mut session_token_path = NamedTempFile::new().unwrap();
         let session_token = random_session_token();
         session_token_path.write_all(session_token.as_bytes()).unwrap();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2020, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(501);
             });
-            assert!(fetcher.get_puzzle_input(day).is_err());
-            mock.assert();
+            let error = fetcher.get_puzzle_input(2020, day).unwrap_err();
+            let status = StatusCode::NOT_IMPLEMENTED;
+            assert!(matches!(error, FetchError::HttpStatus { status: s, .. } if s == status));
+            // The server error is retried before giving up
+            mock.assert_hits(4);
         }
     }
 
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_error_when_site_unreachable() {
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        // Nothing listens on port 1, so the connection is refused
+        let fetcher = create_fetcher(
+            "http://127.0.0.1:1",
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let error = fetcher.get_puzzle_input(2020, 1).unwrap_err();
+        assert!(matches!(error, FetchError::Transport { .. }));
+        assert!(error.source().unwrap().is::<reqwest::Error>());
+    }
+
     //noinspection DuplicatedCode
     #[test]
     fn test_error_when_fetching_from_remote_if_missing_session_token() {
@@ -247,18 +986,19 @@ mod tests {
         let base_url = &server.base_url();
         let puzzle_store_dir = tempdir().unwrap();
         let session_token_path = NamedTempFile::new().unwrap();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str());
+                    .path(remote_url_path(2020, day).as_str());
                 then.status(400)
                     .body("Puzzle inputs differ by user.  Please log in to get your puzzle input.");
             });
-            assert!(fetcher.get_puzzle_input(day).is_err());
+            let error = fetcher.get_puzzle_input(2020, day).unwrap_err();
+            assert!(matches!(error, FetchError::MissingToken { .. }));
             mock.assert_hits(0);
         }
     }
@@ -280,7 +1020,7 @@ mod tests {
         for session_token in session_tokens {
             let mut session_token_path = NamedTempFile::new().unwrap();
             session_token_path.write_all(session_token.as_bytes()).unwrap();
-            let mut fetcher = PuzzleInputFetcher::create_custom(
+            let fetcher = create_fetcher(
                 base_url,
                 puzzle_store_dir.path(),
                 session_token_path.path());
@@ -288,12 +1028,19 @@ mod tests {
                 let puzzle_input = random_puzzle();
                 let mock = server.mock(|when, then| {
                     when.method(GET)
-                        .path(remote_url_path(day).as_str())
+                        .path(remote_url_path(2020, day).as_str())
                         .header("Cookie", format!("session={}", session_token).as_str());
                     then.status(200)
                         .body(puzzle_input);
                 });
-                assert!(fetcher.get_puzzle_input(day).is_err());
+                match fetcher.get_puzzle_input(2020, day).unwrap_err() {
+                    // A blank token counts as no token at all
+                    FetchError::MissingToken { .. } => assert!(session_token.is_empty()),
+                    FetchError::BadTokenFormat { masked_token, .. } => {
+                        assert!(!masked_token.contains(&session_token))
+                    },
+                    e => panic!("Unexpected error: {}", e),
+                }
                 mock.assert_hits(0);
             }
         }
@@ -308,19 +1055,20 @@ mod tests {
         let mut session_token_path = NamedTempFile::new().unwrap();
         let session_token = random_session_token();
         session_token_path.write_all(session_token.as_bytes()).unwrap();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2020, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(400)
                     .body("Puzzle inputs differ by user.  Please log in to get your puzzle input.");
             });
-            assert!(fetcher.get_puzzle_input(day).is_err());
+            let error = fetcher.get_puzzle_input(2020, day).unwrap_err();
+            assert!(matches!(error, FetchError::Unauthorized { .. }));
             mock.assert();
         }
     }
@@ -334,25 +1082,1164 @@ mod tests {
         let mut session_token_path = NamedTempFile::new().unwrap();
         let session_token = random_session_token();
         session_token_path.write_all(session_token.as_bytes()).unwrap();
-        let mut fetcher = PuzzleInputFetcher::create_custom(
+        let fetcher = create_fetcher(
             base_url,
             puzzle_store_dir.path(),
             session_token_path.path());
         for day in 1..26 {
             let mock = server.mock(|when, then| {
                 when.method(GET)
-                    .path(remote_url_path(day).as_str())
+                    .path(remote_url_path(2020, day).as_str())
                     .header("Cookie", format!("session={}", session_token).as_str());
                 then.status(404)
                     .body("Please don't repeatedly request this endpoint before it unlocks! \
                            The calendar countdown is synchronized with the server time; \
                            the link will be enabled on the calendar the instant this puzzle becomes available.");
             });
-            assert!(fetcher.get_puzzle_input(day).is_err());
+            assert!(fetcher.get_puzzle_input(2020, day).is_err());
             mock.assert();
         }
     }
 
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_locked_puzzles_are_not_requested() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(unlock_time(2020, 5) - Duration::from_secs(60)));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone());
+        let mocks = (1..26).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str());
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        let submission = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200)
+                .body(answer_page(5, "That's the right answer!"));
+        });
+        for day in 1..26 {
+            let result = fetcher.get_puzzle_input(2020, day);
+            if day < 5 {
+                assert_eq!(result.unwrap(), numbered_puzzle(day));
+                mocks[(day - 1) as usize].assert();
+            } else {
+                let remaining = unlock_time(2020, day).duration_since(clock.now()).unwrap();
+                match result.unwrap_err() {
+                    FetchError::NotYetUnlocked { year: 2020, day: d, remaining: r } => {
+                        assert_eq!((d, r), (day, remaining));
+                    },
+                    e => panic!("Unexpected error: {}", e),
+                }
+                mocks[(day - 1) as usize].assert_hits(0);
+            }
+        }
+        assert!(fetcher.get_puzzle_description(2020, 5).is_err());
+        assert!(fetcher.submit(2020, 5, 1, &Answer::Unsigned(1)).is_err());
+        submission.assert_hits(0);
+        // Puzzle input we already have can be used regardless
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 6, "stored");
+        assert_eq!(fetcher.get_puzzle_input(2020, 6).unwrap(), "stored");
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_wait_for_unlock() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(unlock_time(2020, 5) - Duration::from_secs(3600)));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone());
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 5).as_str());
+            then.status(200)
+                .body(numbered_puzzle(5));
+        });
+        assert!(fetcher.get_puzzle_input(2020, 5).is_err());
+        assert_eq!(fetcher.wait_for_unlock(2020, 4), Duration::from_secs(0));
+        assert_eq!(fetcher.wait_for_unlock(2020, 5), Duration::from_secs(3600));
+        assert_eq!(clock.now(), unlock_time(2020, 5));
+        assert_eq!(fetcher.get_puzzle_input(2020, 5).unwrap(), numbered_puzzle(5));
+        mock.assert();
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_requests_are_spaced_out() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone())
+            .with_request_policy(RequestPolicy {
+                min_interval: Duration::from_secs(10),
+                ..RequestPolicy::default()
+            });
+        let mocks = (1..4).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str())
+                .header("User-Agent", USER_AGENT);
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        for day in 1..4 {
+            assert_eq!(fetcher.get_puzzle_input(2020, day).unwrap(), numbered_puzzle(day));
+        }
+        mocks.iter().for_each(|mock| mock.assert());
+        // The session check before the first download counts as a request too
+        assert_eq!(clock.sleeps(), vec![Duration::from_secs(10); 3]);
+        // Time that has already passed since the last request counts towards the gap
+        clock.sleep(Duration::from_secs(4));
+        assert!(fetcher.get_puzzle_input(2020, 4).is_err());
+        assert_eq!(clock.sleeps().last(), Some(&Duration::from_secs(6)));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_server_errors_are_retried_with_backoff() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone())
+            .with_request_policy(RequestPolicy {
+                min_interval: Duration::from_secs(0),
+                max_retries: 3,
+                initial_backoff: Duration::from_secs(2),
+                max_backoff: Duration::from_secs(60),
+            });
+        let server_error = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(503);
+        });
+        let client_error = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 2).as_str());
+            then.status(404);
+        });
+        assert!(fetcher.get_puzzle_input(2020, 1).is_err());
+        server_error.assert_hits(4);
+        let backoffs = clock.sleeps().into_iter()
+            .filter(|sleep| *sleep > Duration::from_secs(0))
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs,
+                   vec![Duration::from_secs(2), Duration::from_secs(4), Duration::from_secs(8)]);
+        assert!(fetcher.get_puzzle_input(2020, 2).is_err());
+        client_error.assert_hits(1);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_backoff_is_capped() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone())
+            .with_request_policy(RequestPolicy {
+                min_interval: Duration::from_secs(0),
+                max_retries: 40,
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(30),
+            });
+        let server_error = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path());
+            then.status(503);
+        });
+        // Doubling the backoff 40 times would overflow
+        assert!(fetcher.check_session().is_err());
+        server_error.assert_hits(41);
+        let backoffs = clock.sleeps().into_iter()
+            .filter(|sleep| *sleep > Duration::from_secs(0))
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs.len(), 40);
+        assert_eq!(backoffs[..6], [1, 2, 4, 8, 16, 30].iter()
+            .map(|seconds| Duration::from_secs(*seconds))
+            .collect::<Vec<_>>()[..]);
+        assert!(backoffs[6..].iter().all(|backoff| *backoff == Duration::from_secs(30)));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_years_are_fetched_and_stored_separately() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        for year in 2015..2021 {
+            let puzzle_input = format!("This is the puzzle for {} day 1\n", year);
+            let mock = server.mock(|when, then| {
+                when.method(GET)
+                    .path(remote_url_path(year, 1).as_str())
+                    .header("Cookie", format!("session={}", session_token).as_str());
+                then.status(200)
+                    .body(&puzzle_input);
+            });
+            assert_eq!(fetcher.get_puzzle_input_with_origin(year, 1).unwrap(),
+                       (puzzle_input.clone(), InputOrigin::Remote));
+            let stored_path = puzzle_store_dir.path().join(year.to_string()).join("1");
+            assert_eq!(fs::read_to_string(stored_path).unwrap(), puzzle_input);
+            mock.assert();
+        }
+        for year in 2015..2021 {
+            assert_eq!(fetcher.get_puzzle_input_with_origin(year, 1).unwrap(),
+                       (format!("This is the puzzle for {} day 1\n", year), InputOrigin::Memory));
+        }
+        assert!(fetcher.get_puzzle_input(2014, 1).is_err());
+        assert!(fetcher.get_puzzle_input(2020, 0).is_err());
+        assert!(fetcher.get_puzzle_input(2020, 26).is_err());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_legacy_local_store_is_migrated() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        // Day 1 is only in the old layout, day 2 is in both (the new layout wins)
+        fs::write(puzzle_store_dir.path().join("1"), "old day 1").unwrap();
+        fs::write(puzzle_store_dir.path().join("2"), "old day 2").unwrap();
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 2, "new day 2");
+        let mock = server.mock(|when, then| {
+            when.method(GET);
+            then.status(501);
+        });
+        assert!(fetcher.get_puzzle_input(2019, 1).is_err());
+        assert_eq!(fetcher.get_puzzle_input_with_origin(2020, 1).unwrap(),
+                   ("old day 1".to_string(), InputOrigin::Local));
+        assert_eq!(fetcher.get_puzzle_input_with_origin(2020, 2).unwrap(),
+                   ("new day 2".to_string(), InputOrigin::Local));
+        assert!(!puzzle_store_dir.path().join("1").exists());
+        assert_eq!(fs::read_to_string(puzzle_store_dir.path().join("2020").join("1")).unwrap(),
+                   "old day 1");
+        assert!(puzzle_store_dir.path().join("2").exists());
+        mock.assert_hits(0);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_concurrent_requests_fetch_from_local_store_once() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = Arc::new(create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path()));
+        let mut mocks = Vec::new();
+        for day in 1..26 {
+            store_puzzle_locally(puzzle_store_dir.path(), 2020, day, &numbered_puzzle(day));
+            mocks.push(server.mock(|when, then| {
+                when.method(GET)
+                    .path(remote_url_path(2020, day).as_str());
+                then.status(501);
+            }));
+        }
+        let origins = race_for_all_days(&fetcher);
+        for day in 1..26 {
+            let local_loads = origins.iter()
+                .filter(|(d, origin)| *d == day && *origin == InputOrigin::Local)
+                .count();
+            assert_eq!(local_loads, 1);
+        }
+        mocks.iter().for_each(|mock| mock.assert_hits(0));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_concurrent_requests_fetch_from_remote_store_once() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = Arc::new(create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path()));
+        let mocks = (1..26).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str())
+                .header("Cookie", format!("session={}", session_token).as_str());
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        let origins = race_for_all_days(&fetcher);
+        for day in 1..26 {
+            let remote_loads = origins.iter()
+                .filter(|(d, origin)| *d == day && *origin == InputOrigin::Remote)
+                .count();
+            assert_eq!(remote_loads, 1);
+        }
+        mocks.iter().for_each(|mock| mock.assert_hits(1));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_submit_parses_outcome() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let responses = vec![
+            ("That's the right answer!  You are <span class=\"day-success\">one gold star</span> \
+              closer to saving your vacation.", SubmissionOutcome::Correct),
+            ("That's not the right answer; your answer is too high.  If you're stuck, make sure \
+              you're using the full input data. Please wait one minute before trying again. \
+              (You guessed <span style=\"white-space:nowrap;\"><code>123</code>.)</span>",
+             SubmissionOutcome::TooHigh),
+            ("That's not the right answer; your answer is too low.  Please wait one minute \
+              before trying again.", SubmissionOutcome::TooLow),
+            ("That's not the right answer.  If you're stuck, make sure you're using the full \
+              input data.", SubmissionOutcome::Wrong),
+            ("You gave an answer too recently; you have to wait after submitting an answer \
+              before trying again.  You have 34s left to wait.",
+             SubmissionOutcome::RateLimited { wait: Duration::from_secs(34) }),
+            ("You gave an answer too recently; you have to wait after submitting an answer \
+              before trying again.  You have 4m 5s left to wait.",
+             SubmissionOutcome::RateLimited { wait: Duration::from_secs(245) }),
+            ("You don't seem to be solving the right level.  Did you already complete it?",
+             SubmissionOutcome::AlreadySolved),
+        ];
+        for (day, (message, outcome)) in (1..).zip(responses) {
+            let mock = server.mock(|when, then| {
+                when.method(POST)
+                    .path(submit_url_path(2019, day).as_str())
+                    .header("Cookie", format!("session={}", session_token).as_str())
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .body("level=2&answer=123");
+                then.status(200)
+                    .body(answer_page(day, message));
+            });
+            assert_eq!(fetcher.submit(2019, day, 2, &Answer::Unsigned(123)).unwrap(), outcome);
+            mock.assert();
+        }
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_submit_errors() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let unrecognized = server.mock(|when, then| {
+            when.method(POST)
+                .path(submit_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(answer_page(1, "Something new happened."));
+        });
+        let unavailable = server.mock(|when, then| {
+            when.method(POST)
+                .path(submit_url_path(2020, 2).as_str());
+            then.status(500);
+        });
+        assert!(fetcher.submit(2020, 1, 1, &Answer::from("abc")).is_err());
+        assert!(fetcher.submit(2020, 2, 1, &Answer::from("abc")).is_err());
+        unrecognized.assert();
+        assert!(fetcher.submit(2020, 2, 3, &Answer::from("abc")).is_err());
+        assert!(fetcher.submit(2020, 26, 1, &Answer::from("abc")).is_err());
+        assert!(fetcher.submit(2020, 2, 1, &Answer::Grid(vec!["#.".to_string()])).is_err());
+        unavailable.assert_hits(4);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_submit_requires_session_token() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let mock = server.mock(|when, then| {
+            when.method(POST);
+            then.status(200)
+                .body(answer_page(1, "That's the right answer!"));
+        });
+        assert!(fetcher.submit(2020, 1, 1, &Answer::Unsigned(1)).is_err());
+        mock.assert_hits(0);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_description_from_remote_store() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let page = puzzle_page(&["<p>Part one</p>", "<p>Part two</p>"]);
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(description_url_path(2020, 7).as_str())
+                .header("Cookie", format!("session={}", session_token).as_str());
+            then.status(200)
+                .body(&page);
+        });
+        let description = fetcher.get_puzzle_description(2020, 7).unwrap();
+        assert!(description.is_complete());
+        assert_eq!(description.to_text(), "Part one\n\nPart two\n");
+        let stored_path = puzzle_store_dir.path().join("2020").join("7.html");
+        assert_eq!(fs::read_to_string(stored_path).unwrap(), page);
+        // The complete description is now read from the local store
+        assert_eq!(fetcher.get_puzzle_description(2020, 7).unwrap(), description);
+        mock.assert_hits(1);
+        assert!(fetcher.get_puzzle_description(2020, 26).is_err());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_description_refreshes_part_1_only() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, "unrelated puzzle input");
+        let stored_path = puzzle_store_dir.path().join("2020").join("1.html");
+        fs::write(&stored_path, puzzle_page(&["<p>Part one</p>"])).unwrap();
+        let mut unavailable = server.mock(|when, then| {
+            when.method(GET)
+                .path(description_url_path(2020, 1).as_str());
+            then.status(500);
+        });
+        // Without a session token the request is made anonymously, and if it fails we make do
+        // with part 1
+        let description = fetcher.get_puzzle_description(2020, 1).unwrap();
+        assert!(!description.is_complete());
+        unavailable.assert_hits(4);
+        unavailable.delete();
+        let page = puzzle_page(&["<p>Part one</p>", "<p>Part two</p>"]);
+        let available = server.mock(|when, then| {
+            when.method(GET)
+                .path(description_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(&page);
+        });
+        assert!(fetcher.get_puzzle_description(2020, 1).unwrap().is_complete());
+        assert_eq!(fs::read_to_string(stored_path).unwrap(), page);
+        available.assert();
+        assert!(fetcher.get_puzzle_description(2020, 2).is_err());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_check_session() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let token_source = TokenSource::File(session_token_path.path().to_path_buf());
+        let names = vec!["akaritakai", "(anonymous user #123456)"];
+        for name in names {
+            let mut mock = server.mock(|when, then| {
+                when.method(GET)
+                    .path(account_url_path().as_str())
+                    .header("Cookie", format!("session={}", session_token).as_str());
+                then.status(200)
+                    .body(account_page(Some(name)));
+            });
+            assert_eq!(fetcher.check_session().unwrap(), Account {
+                name: name.to_string(),
+                token_source: token_source.clone(),
+            });
+            mock.assert();
+            mock.delete();
+        }
+        let mut logged_out = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str());
+            then.status(200)
+                .body(account_page(None));
+        });
+        match fetcher.check_session().unwrap_err() {
+            FetchError::Unauthorized { token_source: source, .. } => {
+                assert_eq!(source, token_source)
+            },
+            e => panic!("Unexpected error: {}", e),
+        }
+        logged_out.assert();
+        logged_out.delete();
+        let unavailable = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str());
+            then.status(500);
+        });
+        assert!(fetcher.check_session().is_err());
+        unavailable.assert_hits(4);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_check_session_requires_session_token() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str());
+            then.status(200)
+                .body(account_page(Some("akaritakai")));
+        });
+        assert!(fetcher.check_session().is_err());
+        mock.assert_hits(0);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_session_is_checked_once_before_downloading() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let account = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str())
+                .header("Cookie", format!("session={}", session_token).as_str());
+            then.status(200)
+                .body(account_page(Some("akaritakai")));
+        });
+        let mocks = (1..4).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str());
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        for day in 1..4 {
+            assert_eq!(fetcher.get_puzzle_input(2020, day).unwrap(), numbered_puzzle(day));
+        }
+        account.assert_hits(1);
+        mocks.iter().for_each(|mock| mock.assert());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_nothing_is_downloaded_if_session_rejected() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let account = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str());
+            then.status(200)
+                .body(account_page(None));
+        });
+        let mocks = (1..4).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str());
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        for day in 1..4 {
+            let error = fetcher.get_puzzle_input(2020, day).unwrap_err();
+            assert!(matches!(error, FetchError::Unauthorized { .. }));
+            assert!(error.to_string().contains("The site did not accept the session token from"));
+            assert!(error.to_string().contains(&session_token_path.path().display().to_string()));
+        }
+        account.assert_hits(1);
+        mocks.iter().for_each(|mock| mock.assert_hits(0));
+        assert!(fs::read_dir(puzzle_store_dir.path()).unwrap().next().is_none());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_metadata_is_stored_with_remote_input() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone());
+        let account = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path().as_str());
+            then.status(200)
+                .body(account_page(Some("akaritakai")));
+        });
+        let puzzle_input = random_puzzle();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(&puzzle_input);
+        });
+        fetcher.get_puzzle_input(2020, 1).unwrap();
+        let metadata_path = puzzle_store_dir.path().join("2020").join("1.meta");
+        let metadata = InputMetadata::parse(&fs::read_to_string(metadata_path).unwrap()).unwrap();
+        assert_eq!(metadata.source_url, format!("{}{}", base_url, remote_url_path(2020, 1)));
+        assert_eq!(metadata.length, puzzle_input.len());
+        assert_eq!(metadata.sha256, sha256(&puzzle_input));
+        assert_eq!(metadata.account, Some("akaritakai".to_string()));
+        assert!(metadata.fetched_at <= clock.now());
+        assert_eq!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Valid);
+        account.assert();
+        mock.assert();
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_bad_local_input_is_fetched_again() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let mocks = (1..4).map(|day| server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, day).as_str());
+            then.status(200)
+                .body(numbered_puzzle(day));
+        })).collect::<Vec<_>>();
+        for day in 1..4 {
+            fetcher.get_puzzle_input(2020, day).unwrap();
+        }
+        // Day 1 is truncated, day 2 is an error page saved by hand and day 3 is fine
+        let truncated = numbered_puzzle(1)[..10].to_string();
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, &truncated);
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 2, "<!DOCTYPE html>\n<html></html>");
+        fs::remove_file(puzzle_store_dir.path().join("2020").join("2.meta")).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        assert!(matches!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Corrupt(_)));
+        assert!(matches!(fetcher.verify_puzzle_input(2020, 2), InputStatus::Suspicious(_)));
+        assert_eq!(fetcher.verify_puzzle_input(2020, 3), InputStatus::Valid);
+        assert_eq!(fetcher.verify_puzzle_input(2020, 4), InputStatus::Missing);
+        for day in 1..4 {
+            assert_eq!(fetcher.get_puzzle_input(2020, day).unwrap(), numbered_puzzle(day));
+            assert_eq!(fetcher.verify_puzzle_input(2020, day), InputStatus::Valid);
+        }
+        mocks[0].assert_hits(2);
+        mocks[1].assert_hits(2);
+        mocks[2].assert_hits(1);
+        // Input saved by hand has nothing to be checked against, but is used as it is
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 5, "by hand");
+        assert_eq!(fetcher.verify_puzzle_input(2020, 5), InputStatus::Unverified);
+        assert_eq!(fetcher.get_puzzle_input(2020, 5).unwrap(), "by hand");
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_refetch_replaces_local_input() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, "by hand");
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), "by hand");
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        assert_eq!(fetcher.refetch_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        assert_eq!(fetcher.get_puzzle_input_with_origin(2020, 1).unwrap(),
+                   (numbered_puzzle(1), InputOrigin::Memory));
+        assert_eq!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Valid);
+        mock.assert();
+        assert!(fetcher.refetch_puzzle_input(2020, 26).is_err());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_failed_saves_are_reported() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        // Permissions don't stop the superuser from writing, so a file where the year's directory
+        // should be stands in for a directory that can't be written to
+        fs::write(puzzle_store_dir.path().join("2020"), "not a directory").unwrap();
+        let logger = Arc::new(RecordingLogger::default());
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path())
+            .with_logger(logger.clone());
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        // The input is still returned (and cached), but the failure to save it is reported
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        let warnings = logger.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Failed to save puzzle input for 2020 day 1"));
+        mock.assert_hits(1);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_failed_metadata_save_removes_stale_metadata() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let logger = Arc::new(RecordingLogger::default());
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path())
+            .with_logger(logger.clone());
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, "old input\n");
+        let metadata_path = puzzle_store_dir.path().join("2020/1.meta");
+        let old_metadata = InputMetadata::describe("old input\n", "http://localhost/",
+                                                   SystemTime::now(), None);
+        fs::write(&metadata_path, old_metadata.to_file_contents()).unwrap();
+        assert_eq!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Valid);
+        // A directory where the metadata is first written to makes saving the metadata fail
+        fs::create_dir(puzzle_store_dir.path().join("2020/1.meta.tmp")).unwrap();
+        assert_eq!(fetcher.refetch_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        assert_eq!(fs::read_to_string(puzzle_store_dir.path().join("2020/1")).unwrap(),
+                   numbered_puzzle(1));
+        // The new input is not checked against the old metadata
+        assert!(!metadata_path.exists());
+        assert_eq!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Unverified);
+        let warnings = logger.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Failed to save puzzle input metadata for 2020 day 1"));
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_interrupted_write_is_not_used() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let logger = Arc::new(RecordingLogger::default());
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path())
+            .with_logger(logger.clone());
+        // A write that was interrupted leaves part of the input in the temporary file only
+        let year_dir = puzzle_store_dir.path().join("2020");
+        fs::create_dir_all(&year_dir).unwrap();
+        fs::write(year_dir.join("1.tmp"), &numbered_puzzle(1)[..10]).unwrap();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        assert_eq!(fetcher.get_puzzle_input_with_origin(2020, 1).unwrap(),
+                   (numbered_puzzle(1), InputOrigin::Remote));
+        assert_eq!(fs::read_to_string(year_dir.join("1")).unwrap(), numbered_puzzle(1));
+        assert!(!year_dir.join("1.tmp").exists());
+        assert!(!year_dir.join("1.meta.tmp").exists());
+        assert_eq!(fetcher.verify_puzzle_input(2020, 1), InputStatus::Valid);
+        mock.assert();
+        assert!(logger.warnings().is_empty());
+        // A failed write leaves the previous contents in place
+        let path = year_dir.join("2");
+        write_atomically(&path, "first").unwrap();
+        fs::create_dir(year_dir.join("2.tmp")).unwrap();
+        assert!(write_atomically(&path, "second").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_private_leaderboard() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path(),
+            clock.clone());
+        let json = fs::read_to_string("resources/tests/leaderboard.json").unwrap();
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(leaderboard_url_path(2020, 111111).as_str())
+                .header("Cookie", format!("session={}", session_token).as_str());
+            then.status(200)
+                .body(&json);
+        });
+        let leaderboard = fetcher.get_private_leaderboard(2020, 111111).unwrap();
+        assert_eq!(leaderboard.owner_id, 111111);
+        assert_eq!(leaderboard.standings().iter()
+                       .map(|member| member.display_name())
+                       .collect::<Vec<_>>(),
+                   vec!["akaritakai", "(anonymous user #222222)", "Bob", "Idle"]);
+        let stored_path = puzzle_store_dir.path().join("2020/leaderboard/111111.json");
+        assert_eq!(fs::read_to_string(stored_path).unwrap(), json);
+        // The stored leaderboard is used until it is 15 minutes old
+        clock.sleep(Duration::from_secs(14 * 60));
+        assert_eq!(fetcher.get_private_leaderboard(2020, 111111).unwrap(), leaderboard);
+        mock.assert_hits(1);
+        clock.sleep(Duration::from_secs(2 * 60));
+        assert_eq!(fetcher.get_private_leaderboard(2020, 111111).unwrap(), leaderboard);
+        mock.assert_hits(2);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_fetch_private_leaderboard_errors() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        // Those who can't see the leaderboard are sent to a page instead
+        let mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(leaderboard_url_path(2020, 222222).as_str());
+            then.status(200)
+                .body(account_page(Some("akaritakai")));
+        });
+        let error = fetcher.get_private_leaderboard(2020, 222222).unwrap_err();
+        assert!(matches!(error, FetchError::MissingToken { .. }));
+        mock.assert_hits(0);
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path());
+        let error = fetcher.get_private_leaderboard(2020, 222222).unwrap_err();
+        assert!(matches!(error, FetchError::UnexpectedResponse(_)));
+        assert!(error.to_string().contains("member of the leaderboard"));
+        mock.assert_hits(1);
+        assert!(!puzzle_store_dir.path().join("2020/leaderboard/222222.json").exists());
+        assert!(fetcher.get_private_leaderboard(2014, 222222).is_err());
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_offline_only_uses_local_store() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        // There is no session token, and none is needed
+        let session_token_path = puzzle_store_dir.path().join("cookie.txt");
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            &session_token_path)
+            .with_fetch_policy(FetchPolicy::OfflineOnly);
+        let mock = server.mock(|when, then| {
+            when.method(GET);
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, &numbered_puzzle(1));
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        let error = fetcher.get_puzzle_input(2020, 2).unwrap_err();
+        let expected_path = puzzle_store_dir.path().join("2020").join("2");
+        assert!(matches!(&error, FetchError::Offline { path: Some(path), .. }
+                         if *path == expected_path));
+        assert!(error.to_string().contains(&expected_path.display().to_string()));
+        // Bad input is not replaced
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 3, "");
+        let error = fetcher.get_puzzle_input(2020, 3).unwrap_err();
+        assert!(matches!(error, FetchError::CorruptLocalInput { day: 3,
+                                                                 status: InputStatus::Suspicious(_),
+                                                                 .. }));
+        assert!(matches!(fetcher.get_puzzle_description(2020, 1).unwrap_err(),
+                         FetchError::Offline { path: Some(_), .. }));
+        assert!(matches!(fetcher.get_private_leaderboard(2020, 111111).unwrap_err(),
+                         FetchError::Offline { path: Some(_), .. }));
+        assert!(matches!(fetcher.check_session().unwrap_err(),
+                         FetchError::Offline { path: None, .. }));
+        assert!(matches!(fetcher.submit(2020, 1, 1, &Answer::from(1)).unwrap_err(),
+                         FetchError::Offline { path: None, .. }));
+        mock.assert_hits(0);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_offline_only_uses_stale_leaderboard() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let session_token_path = puzzle_store_dir.path().join("cookie.txt");
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        let fetcher = PuzzleInputFetcher::create_with_clock(
+            base_url,
+            puzzle_store_dir.path(),
+            &session_token_path,
+            clock.clone())
+            .with_fetch_policy(FetchPolicy::OfflineOnly);
+        let json = fs::read_to_string("resources/tests/leaderboard.json").unwrap();
+        let leaderboard_dir = puzzle_store_dir.path().join("2020/leaderboard");
+        fs::create_dir_all(&leaderboard_dir).unwrap();
+        fs::write(leaderboard_dir.join("111111.json"), json).unwrap();
+        clock.sleep(Duration::from_secs(24 * 60 * 60));
+        assert_eq!(fetcher.get_private_leaderboard(2020, 111111).unwrap().owner_id, 111111);
+    }
+
+    //noinspection DuplicatedCode
+    #[test]
+    fn test_remote_refresh_ignores_local_store() {
+        let server = MockServer::start();
+        let base_url = &server.base_url();
+        let puzzle_store_dir = tempdir().unwrap();
+        let mut session_token_path = NamedTempFile::new().unwrap();
+        let session_token = random_session_token();
+        session_token_path.write_all(session_token.as_bytes()).unwrap();
+        let fetcher = create_fetcher(
+            base_url,
+            puzzle_store_dir.path(),
+            session_token_path.path())
+            .with_fetch_policy(FetchPolicy::RemoteRefresh);
+        let account_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(account_url_path());
+            then.status(200)
+                .body(account_page(Some("akaritakai")));
+        });
+        let input_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(remote_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(numbered_puzzle(1));
+        });
+        let description_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(description_url_path(2020, 1).as_str());
+            then.status(200)
+                .body(puzzle_page(&["Part 1", "Part 2"]));
+        });
+        store_puzzle_locally(puzzle_store_dir.path(), 2020, 1, "old");
+        fs::write(puzzle_store_dir.path().join("2020/1.html"), puzzle_page(&["Old", "Old"]))
+            .unwrap();
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        assert_eq!(fetcher.get_puzzle_input(2020, 1).unwrap(), numbered_puzzle(1));
+        assert_eq!(fs::read_to_string(puzzle_store_dir.path().join("2020/1")).unwrap(),
+                   numbered_puzzle(1));
+        assert!(fetcher.get_puzzle_description(2020, 1).unwrap().to_markdown().contains("Part 2"));
+        account_mock.assert_hits(1);
+        input_mock.assert_hits(1);
+        description_mock.assert_hits(1);
+    }
+
+    // Keeps the warnings it is given so that they can be checked
+    #[derive(Default)]
+    struct RecordingLogger {
+        warnings: Mutex<Vec<String>>,
+    }
+
+    impl RecordingLogger {
+        fn warnings(&self) -> Vec<String> {
+            self.warnings.lock().unwrap().clone()
+        }
+    }
+
+    impl Logger for RecordingLogger {
+        fn warn(&self, message: &str) {
+            self.warnings.lock().unwrap().push(message.to_string());
+        }
+    }
+
+    // Creates a fetcher whose clock starts at the current time but doesn't sleep for real, so that
+    // pacing requests and backing off doesn't slow the tests down
+    fn create_fetcher(base_url: &str, input_path: &Path, session_token_path: &Path)
+        -> PuzzleInputFetcher {
+        let clock = Arc::new(FakeClock::at(SystemTime::now()));
+        PuzzleInputFetcher::create_with_clock(base_url, input_path, session_token_path, clock)
+    }
+
+    fn puzzle_page(parts: &[&str]) -> String {
+        let articles = parts.iter()
+            .map(|part| format!("<article class=\"day-desc\">{}</article>", part))
+            .collect::<Vec<_>>()
+            .join("\n<p>Your puzzle answer was <code>1</code>.</p>");
+        format!("<!DOCTYPE html>\n<html lang=\"en-us\"><body><main>\n{}\n</main></body></html>",
+                articles)
+    }
+
+    // A page as shown to the given user (or to someone who isn't logged in)
+    fn account_page(name: Option<&str>) -> String {
+        let header = match name {
+            Some(name) => format!("<div class=\"user\">{} <span class=\"star-count\">50*</span>\
+                                   </div>", name),
+            None => "<div><a href=\"/2020/auth/login\">[Log In]</a></div>".to_string(),
+        };
+        format!("<!DOCTYPE html>\n<html lang=\"en-us\"><body><header><h1><a href=\"/\">\
+                 Advent of Code</a></h1>{}</header><main></main></body></html>", header)
+    }
+
+    // The page the site sends back after submitting an answer, with the given message
+    fn answer_page(day: u8, message: &str) -> String {
+        format!("<!DOCTYPE html>\n<html lang=\"en-us\"><head><title>Day {} - Advent of Code</title>\
+                 </head><body><header><h1><a href=\"/\">Advent of Code</a></h1></header>\
+                 <main>\n<article><p>{} <a href=\"/2020/day/{}\">[Return to Day {}]</a></p>\
+                 </article>\n</main></body></html>", day, message, day, day)
+    }
+
+    // Has several threads request every day at the same time (each starting from a different day)
+    // and checks they all get the right input, returning where each request was served from
+    fn race_for_all_days(fetcher: &Arc<PuzzleInputFetcher>) -> Vec<(u8, InputOrigin)> {
+        let threads = (0..16u8).map(|i| {
+            let fetcher = fetcher.clone();
+            thread::spawn(move || {
+                (0..25u8).map(|j| {
+                    let day = (i + j) % 25 + 1;
+                    let (input, origin) = fetcher.get_puzzle_input_with_origin(2020, day).unwrap();
+                    assert_eq!(input, numbered_puzzle(day));
+                    (day, origin)
+                }).collect::<Vec<_>>()
+            })
+        }).collect::<Vec<_>>();
+        threads.into_iter()
+            .flat_map(|thread| thread.join().unwrap())
+            .collect()
+    }
+
+    fn store_puzzle_locally(puzzle_store_dir: &Path, year: u16, day: u8, puzzle_input: &str) {
+        let year_dir = puzzle_store_dir.join(year.to_string());
+        fs::create_dir_all(&year_dir).unwrap();
+        fs::write(year_dir.join(day.to_string()), puzzle_input).unwrap();
+    }
+
+    fn numbered_puzzle(day: u8) -> String {
+        format!("This is the puzzle for day {}\n", day).repeat(100)
+    }
+
     fn random_puzzle() -> String {
         // Puzzle inputs tend to contain a wide variety of ASCII characters including line feed.
         // They can also be fairly large.
@@ -0,0 +1,107 @@
+use crate::puzzle::{AbstractPuzzle, Result};
+use std::collections::BTreeMap;
+
+pub type PuzzleConstructor = fn(&str) -> Result<Box<dyn AbstractPuzzle>>;
+
+pub struct PuzzleRegistry {
+    // The puzzle constructors keyed by (year, day)
+    constructors: BTreeMap<(u16, u8), PuzzleConstructor>,
+}
+
+impl PuzzleRegistry {
+    // Creates a PuzzleRegistry containing every puzzle solved in this project (as listed in
+    // lib.rs)
+    pub fn create() -> PuzzleRegistry {
+        let mut registry = PuzzleRegistry::empty();
+        crate::register_puzzles(&mut registry);
+        registry
+    }
+
+    // Creates a PuzzleRegistry with no puzzles in it
+    pub fn empty() -> PuzzleRegistry {
+        PuzzleRegistry {
+            constructors: BTreeMap::new(),
+        }
+    }
+
+    // Registers the constructor for the puzzle of the given year and day. Registering the same
+    // puzzle twice is a programming error.
+    pub fn register(&mut self, year: u16, day: u8, constructor: PuzzleConstructor) {
+        if self.constructors.insert((year, day), constructor).is_some() {
+            panic!("Puzzle for {} day {} was registered twice", year, day);
+        }
+    }
+
+    // Returns the constructor for the puzzle of the given year and day (if it is implemented)
+    pub fn get(&self, year: u16, day: u8) -> Option<PuzzleConstructor> {
+        self.constructors.get(&(year, day)).copied()
+    }
+
+    pub fn is_implemented(&self, year: u16, day: u8) -> bool {
+        self.constructors.contains_key(&(year, day))
+    }
+
+    // Returns the implemented days of the given year in ascending order
+    pub fn days(&self, year: u16) -> Vec<u8> {
+        self.constructors.keys()
+            .filter(|(y, _)| *y == year)
+            .map(|(_, day)| *day)
+            .collect()
+    }
+
+    // Returns the years that have at least one implemented day in ascending order
+    pub fn years(&self) -> Vec<u16> {
+        let mut years = self.constructors.keys()
+            .map(|(year, _)| *year)
+            .collect::<Vec<_>>();
+        years.dedup();
+        years
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::answer::Answer;
+    use crate::puzzle_registry::PuzzleRegistry;
+    use crate::puzzle01::Puzzle01;
+    use crate::puzzle03::Puzzle03;
+
+    #[test]
+    fn test_default_registry_contains_solved_days() {
+        let registry = PuzzleRegistry::create();
+        assert_eq!(registry.days(2020), vec![1, 2, 3, 4, 5]);
+        assert!(registry.is_implemented(2020, 1));
+        assert!(!registry.is_implemented(2020, 25));
+        assert!(!registry.is_implemented(2019, 1));
+        assert!(registry.days(2019).is_empty());
+        assert_eq!(registry.years(), vec![2020]);
+    }
+
+    #[test]
+    fn test_lookup_constructs_the_registered_puzzle() {
+        let registry = PuzzleRegistry::create();
+        let puzzle = registry.get(2020, 1).unwrap()("1721\n979\n366\n299\n675\n1456").unwrap();
+        assert_eq!(puzzle.get_day(), 1);
+        assert_eq!(puzzle.solve_part_1().unwrap(), Answer::Unsigned(514579));
+        assert!(registry.get(2020, 6).is_none());
+    }
+
+    #[test]
+    fn test_days_are_ordered_and_keyed_by_year() {
+        let mut registry = PuzzleRegistry::empty();
+        registry.register(2020, 3, Puzzle03::create);
+        registry.register(2019, 7, Puzzle01::create);
+        registry.register(2020, 1, Puzzle01::create);
+        assert_eq!(registry.days(2020), vec![1, 3]);
+        assert_eq!(registry.days(2019), vec![7]);
+        assert_eq!(registry.years(), vec![2019, 2020]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_registration_fails() {
+        let mut registry = PuzzleRegistry::empty();
+        registry.register(2020, 1, Puzzle01::create);
+        registry.register(2020, 1, Puzzle01::create);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::answer::{format_answer, Answer};
+use crate::cli::{self, RunOptions};
+use crate::input_normalizer::NormalizedInput;
+use crate::input_source::InputSource;
+use crate::puzzle_input_fetcher::InputOrigin;
+use crate::puzzle_registry::PuzzleRegistry;
+use crate::timing::{self, DayTiming, Stats};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::time::Instant;
+
+// The outcome of solving a single day. Output is collected rather than printed straight away so
+// that days solved in parallel can still be reported in day order.
+pub struct DayReport {
+    pub day: u8,
+
+    // The lines to report in order: answers are Ok and failures are Err
+    pub output: Vec<Result<String, String>>,
+
+    // Problems that didn't stop the day from being solved, e.g. input that had to be normalized
+    // (which is only reported in strict mode)
+    pub warnings: Vec<String>,
+
+    // Absent if the input could not be fetched
+    pub timing: Option<DayTiming>,
+}
+
+impl DayReport {
+    pub fn failed(&self) -> bool {
+        self.output.iter().any(Result::is_err)
+    }
+}
+
+// Solves each of the given days with input from the given source (usually default_chain),
+// handing every report to the callback in day order. With more than one job, days are fetched and
+// solved on a thread pool of that size.
+pub fn run_days<F>(registry: &PuzzleRegistry, source: &dyn InputSource, days: &[u8],
+                   options: &RunOptions, callback: F) -> Result<(), String>
+    where F: FnMut(DayReport) {
+    let iterations = options.iterations.unwrap_or(1);
+    let solve = |day: &u8| solve_day(registry, source, *day, options, iterations);
+    if options.jobs > 1 {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(options.jobs)
+            .build()
+            .map_err(|e| format!("Failed to start {} jobs: {}", options.jobs, e))?;
+        pool.install(|| days.par_iter().map(solve).collect::<Vec<_>>())
+            .into_iter()
+            .for_each(callback);
+    } else {
+        days.iter().map(solve).for_each(callback);
+    }
+    Ok(())
+}
+
+// Fetches, parses and solves the selected parts of a single day, timing each phase over the given
+// number of iterations. If asked to, waits for the day to unlock first.
+fn solve_day(registry: &PuzzleRegistry, source: &dyn InputSource, day: u8,
+             options: &RunOptions, iterations: usize) -> DayReport {
+    let year = options.year;
+    let mut report = DayReport {
+        day,
+        output: Vec::new(),
+        warnings: Vec::new(),
+        timing: None,
+    };
+    if options.wait {
+        source.wait_for_input(year, day);
+    }
+    let start = Instant::now();
+    let (normalized, origin) = match read_input(source, year, day, options.strict_input) {
+        Ok(result) => result,
+        Err(e) => {
+            report.output.push(Err(format!("Day {:02}: {}", day, e)));
+            return report;
+        }
+    };
+    if options.strict_input {
+        report.warnings.extend(normalized.describe_changes(year, day));
+    }
+    let input = normalized.input;
+    let mut timing = DayTiming {
+        day,
+        origin,
+        fetch: start.elapsed(),
+        parse: None,
+        part_1: None,
+        part_2: None,
+    };
+    let constructor = registry.get(year, day).unwrap();
+    let (puzzle, samples) = timing::measure(iterations, || constructor(&input));
+    let puzzle = match puzzle {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            report.output.push(Err(format!("Day {:02}: {}", day, e)));
+            report.timing = Some(timing);
+            return report;
+        }
+    };
+    timing.parse = Some(Stats::from_samples(&samples));
+    for part in &options.parts {
+        let (result, samples) = match part {
+            1 => timing::measure(iterations, || puzzle.solve_part_1()),
+            _ => timing::measure(iterations, || puzzle.solve_part_2()),
+        };
+        match result {
+            Ok(answer) => {
+                let label = format!("Day {:02} Part {}", puzzle.get_day(), part);
+                report.output.push(Ok(format_answer(&label, &answer)));
+                let stats = Some(Stats::from_samples(&samples));
+                match part {
+                    1 => timing.part_1 = stats,
+                    _ => timing.part_2 = stats,
+                }
+            },
+            Err(e) => report.output.push(Err(format!("Day {:02} Part {}: {}", day, part, e))),
+        }
+    }
+    report.timing = Some(timing);
+    report
+}
+
+// Solves a single part of a day with input from the given source, e.g. to submit the answer
+pub fn solve_part(registry: &PuzzleRegistry, source: &dyn InputSource, year: u16, day: u8,
+                  part: u8) -> Result<Answer, String> {
+    let constructor = registry.get(year, day)
+        .ok_or_else(|| format!("No solution is available for {} day {}", year, day))?;
+    let (normalized, _) = read_input(source, year, day, false)?;
+    let puzzle = constructor(&normalized.input).map_err(|e| e.to_string())?;
+    let answer = match part {
+        1 => puzzle.solve_part_1(),
+        _ => puzzle.solve_part_2(),
+    };
+    answer.map_err(|e| e.to_string())
+}
+
+// Gets the normalized input for a day from the given source, explaining any failure
+fn read_input(source: &dyn InputSource, year: u16, day: u8, strict: bool)
+    -> Result<(NormalizedInput, InputOrigin), String> {
+    source.get_normalized_input(year, day, strict).map_err(|e| cli::explain(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::RunOptions;
+    use crate::input_source::{default_chain, MemorySource};
+    use crate::puzzle_input_fetcher::{InputOrigin, PuzzleInputFetcher};
+    use crate::puzzle_registry::PuzzleRegistry;
+    use crate::answer::Answer;
+    use crate::runner::{run_days, solve_part, DayReport};
+
+    use std::fs;
+    use std::sync::Arc;
+    use tempfile::{tempdir, NamedTempFile};
+
+    fn run_all(days: &[u8], jobs: usize) -> Vec<DayReport> {
+        let puzzle_store_dir = tempdir().unwrap();
+        let year_dir = puzzle_store_dir.path().join("2020");
+        fs::create_dir(&year_dir).unwrap();
+        for day in 1..=5 {
+            fs::copy(format!("resources/tests/{}", day), year_dir.join(day.to_string())).unwrap();
+        }
+        fs::write(year_dir.join("3"), "..#\n.X.\n").unwrap();
+        let session_token_path = NamedTempFile::new().unwrap();
+        let fetcher = Arc::new(PuzzleInputFetcher::create_custom(
+            "http://localhost:1",
+            puzzle_store_dir.path(),
+            session_token_path.path()));
+        let options = RunOptions {
+            jobs,
+            ..RunOptions::default()
+        };
+        let mut reports = Vec::new();
+        run_days(&PuzzleRegistry::create(), &default_chain(&fetcher), days, &options,
+                 |report| reports.push(report)).unwrap();
+        reports
+    }
+
+    #[test]
+    fn test_reports_are_in_day_order_and_keep_going_after_failures() {
+        let reports = run_all(&[1, 2, 3, 4, 5], 1);
+        assert_eq!(reports.iter().map(|report| report.day).collect::<Vec<_>>(),
+                   vec![1, 2, 3, 4, 5]);
+        assert_eq!(reports[0].output, vec![
+            Ok("Day 01 Part 1: 440979".to_string()),
+            Ok("Day 01 Part 2: 82498112".to_string()),
+        ]);
+        assert!(reports[2].failed());
+        assert!(reports[2].timing.as_ref().unwrap().parse.is_none());
+        assert!(!reports[4].failed());
+    }
+
+    #[test]
+    fn test_parallel_run_matches_sequential_run() {
+        let sequential = run_all(&[1, 2, 3, 4, 5], 1);
+        for jobs in 2..=5 {
+            let parallel = run_all(&[1, 2, 3, 4, 5], jobs);
+            assert_eq!(parallel.iter().map(|report| report.day).collect::<Vec<_>>(),
+                       vec![1, 2, 3, 4, 5]);
+            for (a, b) in sequential.iter().zip(parallel.iter()) {
+                assert_eq!(a.output, b.output);
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_is_normalized() {
+        let source = MemorySource::new()
+            .with_input(2020, 1, "1721\r\n979\r\n366\r\n299\r\n675\r\n1456\r\n");
+        let options = RunOptions {
+            parts: vec![1],
+            ..RunOptions::default()
+        };
+        let mut reports = Vec::new();
+        run_days(&PuzzleRegistry::create(), &source, &[1], &options,
+                 |report| reports.push(report)).unwrap();
+        assert_eq!(reports[0].output, vec![Ok("Day 01 Part 1: 514579".to_string())]);
+        assert!(reports[0].warnings.is_empty());
+        let options = RunOptions {
+            strict_input: true,
+            ..options
+        };
+        let mut reports = Vec::new();
+        run_days(&PuzzleRegistry::create(), &source, &[1], &options,
+                 |report| reports.push(report)).unwrap();
+        assert_eq!(reports[0].output, vec![Ok("Day 01 Part 1: 514579".to_string())]);
+        assert_eq!(reports[0].warnings, vec![
+            "Puzzle input for 2020 day 1 was normalized: converted 6 CRLF line ending(s) to LF",
+        ]);
+    }
+
+    #[test]
+    fn test_solve_part() {
+        let registry = PuzzleRegistry::create();
+        let source = MemorySource::new()
+            .with_input(2020, 1, "1721\r\n979\r\n366\r\n299\r\n675\r\n1456\r\n");
+        assert_eq!(solve_part(&registry, &source, 2020, 1, 1).unwrap(), Answer::Unsigned(514579));
+        assert_eq!(solve_part(&registry, &source, 2020, 1, 2).unwrap(),
+                   Answer::Unsigned(241861950));
+        assert_eq!(solve_part(&registry, &source, 2020, 2, 1).unwrap_err(),
+                   "No puzzle input for 2020 day 2 was found in memory");
+    }
+
+    #[test]
+    fn test_run_with_input_from_memory() {
+        let source = MemorySource::new()
+            .with_input(2020, 1, "1721\n979\n366\n299\n675\n1456\n");
+        let mut reports = Vec::new();
+        run_days(&PuzzleRegistry::create(), &source, &[1, 2], &RunOptions::default(),
+                 |report| reports.push(report)).unwrap();
+        assert_eq!(reports[0].output, vec![
+            Ok("Day 01 Part 1: 514579".to_string()),
+            Ok("Day 01 Part 2: 241861950".to_string()),
+        ]);
+        assert_eq!(reports[0].timing.as_ref().unwrap().origin, InputOrigin::Memory);
+        assert_eq!(reports[1].output, vec![
+            Err("Day 02: No puzzle input for 2020 day 2 was found in memory".to_string()),
+        ]);
+    }
+}
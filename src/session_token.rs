@@ -0,0 +1,305 @@
+use crate::fetch_error::FetchError;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// The environment variable that can hold the session token
+pub const SESSION_TOKEN_VAR: &str = "AOC_SESSION";
+
+// Where a session token came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenSource {
+    // Given on the command line
+    Flag,
+
+    Environment(String),
+    File(PathBuf),
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenSource::Flag => write!(f, "the --session-token flag"),
+            TokenSource::Environment(var) => write!(f, "the {} environment variable", var),
+            TokenSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionToken {
+    pub token: String,
+    pub source: TokenSource,
+}
+
+// A place a session token might be found
+pub trait SessionTokenProvider: Send + Sync {
+    fn source(&self) -> TokenSource;
+
+    // Returns the token (if this provider has one). Blank tokens count as not having one.
+    fn token(&self) -> Result<Option<String>>;
+}
+
+pub struct FlagProvider {
+    token: String,
+}
+
+impl FlagProvider {
+    pub fn new(token: &str) -> FlagProvider {
+        FlagProvider { token: token.to_string() }
+    }
+}
+
+impl SessionTokenProvider for FlagProvider {
+    fn source(&self) -> TokenSource {
+        TokenSource::Flag
+    }
+
+    fn token(&self) -> Result<Option<String>> {
+        Ok(non_blank(&self.token))
+    }
+}
+
+pub struct EnvironmentProvider {
+    var: String,
+}
+
+impl EnvironmentProvider {
+    pub fn new(var: &str) -> EnvironmentProvider {
+        EnvironmentProvider { var: var.to_string() }
+    }
+}
+
+impl SessionTokenProvider for EnvironmentProvider {
+    fn source(&self) -> TokenSource {
+        TokenSource::Environment(self.var.clone())
+    }
+
+    fn token(&self) -> Result<Option<String>> {
+        match env::var(&self.var) {
+            Ok(token) => Ok(non_blank(&token)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(env::VarError::NotUnicode(token)) => Err(FetchError::BadTokenFormat {
+                token_source: self.source(),
+                masked_token: mask(&token.to_string_lossy()),
+            }),
+        }
+    }
+}
+
+pub struct FileProvider {
+    path: PathBuf,
+}
+
+impl FileProvider {
+    pub fn new(path: &Path) -> FileProvider {
+        FileProvider { path: path.to_path_buf() }
+    }
+}
+
+impl SessionTokenProvider for FileProvider {
+    fn source(&self) -> TokenSource {
+        TokenSource::File(self.path.clone())
+    }
+
+    fn token(&self) -> Result<Option<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(token) => Ok(non_blank(&token)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(FetchError::Io {
+                context: format!("Failed to read session token from {}", self.source()),
+                source: e,
+            }),
+        }
+    }
+}
+
+// The providers to try in order: the command line flag (if given), the AOC_SESSION environment
+// variable, the config file (usually ~/.config/aoc/session) and finally the project's token file
+pub fn default_providers(flag: Option<&str>, project_file: &Path)
+    -> Vec<Box<dyn SessionTokenProvider>> {
+    let mut providers: Vec<Box<dyn SessionTokenProvider>> = Vec::new();
+    if let Some(token) = flag {
+        providers.push(Box::new(FlagProvider::new(token)));
+    }
+    providers.push(Box::new(EnvironmentProvider::new(SESSION_TOKEN_VAR)));
+    if let Some(path) = config_file_path(env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+        providers.push(Box::new(FileProvider::new(&path)));
+    }
+    providers.push(Box::new(FileProvider::new(project_file)));
+    providers
+}
+
+// Returns the first token found by the providers, checking that it looks like a session token
+pub fn find_session_token(providers: &[Box<dyn SessionTokenProvider>]) -> Result<SessionToken> {
+    for provider in providers {
+        if let Some(token) = provider.token()? {
+            let has_right_length = token.len() == 96;
+            let has_right_charset = token.chars()
+                .all(|x| x.is_ascii_digit() || x.is_ascii_lowercase());
+            return if !has_right_length || !has_right_charset {
+                Err(FetchError::BadTokenFormat {
+                    token_source: provider.source(),
+                    masked_token: mask(&token),
+                })
+            } else {
+                Ok(SessionToken { token, source: provider.source() })
+            };
+        }
+    }
+    Err(FetchError::MissingToken {
+        looked_in: providers.iter().map(|provider| provider.source()).collect(),
+    })
+}
+
+// Hides all but the ends of a token so that it can be shown in messages. The ends are only shown
+// for tokens long enough that they give little of the token away (real tokens are much longer).
+pub fn mask(token: &str) -> String {
+    let chars = token.chars().collect::<Vec<_>>();
+    if chars.len() < 32 {
+        format!("{} ({} characters)", "*".repeat(chars.len()), chars.len())
+    } else {
+        format!("{}...{} ({} characters)",
+                chars[..4].iter().collect::<String>(),
+                chars[(chars.len() - 4)..].iter().collect::<String>(),
+                chars.len())
+    }
+}
+
+fn config_file_path(xdg_config_home: Option<OsString>, home: Option<OsString>)
+    -> Option<PathBuf> {
+    let config_dir = match xdg_config_home.filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(home.filter(|dir| !dir.is_empty())?).join(".config"),
+    };
+    Some(config_dir.join("aoc").join("session"))
+}
+
+fn non_blank(token: &str) -> Option<String> {
+    Some(token.trim().to_string()).filter(|token| !token.is_empty())
+}
+
+type Result<T> = std::result::Result<T, FetchError>;
+
+#[cfg(test)]
+mod tests {
+    use crate::fetch_error::FetchError;
+    use crate::session_token::{config_file_path, find_session_token, mask, EnvironmentProvider,
+                               FileProvider, FlagProvider, SessionToken, SessionTokenProvider,
+                               TokenSource};
+
+    use rand::Rng;
+    use std::env;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_providers_are_tried_in_order() {
+        let dir = tempdir().unwrap();
+        let missing_file = dir.path().join("missing");
+        let blank_file = dir.path().join("blank");
+        let token_file = dir.path().join("token");
+        fs::write(&blank_file, "\n").unwrap();
+        let token = random_session_token();
+        fs::write(&token_file, format!("{}\n", token)).unwrap();
+        let var = "AOC_SESSION_TEST_PROVIDERS_ARE_TRIED_IN_ORDER";
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(FlagProvider::new("")),
+            Box::new(EnvironmentProvider::new(var)),
+            Box::new(FileProvider::new(&missing_file)),
+            Box::new(FileProvider::new(&blank_file)),
+            Box::new(FileProvider::new(&token_file)),
+        ];
+        assert_eq!(find_session_token(&providers).unwrap(), SessionToken {
+            token: token.clone(),
+            source: TokenSource::File(token_file.clone()),
+        });
+        let env_token = random_session_token();
+        env::set_var(var, &env_token);
+        assert_eq!(find_session_token(&providers).unwrap(), SessionToken {
+            token: env_token,
+            source: TokenSource::Environment(var.to_string()),
+        });
+        env::remove_var(var);
+        let flag_token = random_session_token();
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(FlagProvider::new(&flag_token)),
+            Box::new(FileProvider::new(&token_file)),
+        ];
+        assert_eq!(find_session_token(&providers).unwrap(), SessionToken {
+            token: flag_token,
+            source: TokenSource::Flag,
+        });
+    }
+
+    #[test]
+    fn test_errors_name_the_source_and_mask_the_token() {
+        let dir = tempdir().unwrap();
+        let token_file = dir.path().join("token");
+        let mut token = random_session_token();
+        token.truncate(95);
+        fs::write(&token_file, &token).unwrap();
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(FileProvider::new(&token_file)),
+        ];
+        let error = find_session_token(&providers).unwrap_err().to_string();
+        assert!(error.contains(&token_file.display().to_string()));
+        assert!(error.contains(&mask(&token)));
+        assert!(!error.contains(&token));
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(FlagProvider::new("not a token")),
+        ];
+        let error = find_session_token(&providers).unwrap_err().to_string();
+        assert!(error.contains("--session-token flag"));
+        assert!(!error.contains("not a token"));
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(EnvironmentProvider::new("AOC_SESSION_TEST_ERRORS_NAME_THE_SOURCE")),
+            Box::new(FileProvider::new(&dir.path().join("missing"))),
+        ];
+        let error = find_session_token(&providers).unwrap_err().to_string();
+        assert!(error.contains("the AOC_SESSION_TEST_ERRORS_NAME_THE_SOURCE environment variable"));
+        assert!(error.contains(&dir.path().join("missing").display().to_string()));
+        // A token file that can't be read (here because it's a directory) is an error rather than
+        // being skipped
+        let providers: Vec<Box<dyn SessionTokenProvider>> = vec![
+            Box::new(FileProvider::new(dir.path())),
+            Box::new(FileProvider::new(&token_file)),
+        ];
+        assert!(matches!(find_session_token(&providers).unwrap_err(), FetchError::Io { .. }));
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("0123456789abcdef0123456789abcdef"), "0123...cdef (32 characters)");
+        assert_eq!(mask("0123456789abcdef0123456789abcde"),
+                   format!("{} (31 characters)", "*".repeat(31)));
+        assert_eq!(mask("0123456789abcdef"), "**************** (16 characters)");
+        assert_eq!(mask("secret"), "****** (6 characters)");
+        assert_eq!(mask(""), " (0 characters)");
+    }
+
+    #[test]
+    fn test_config_file_path() {
+        let os = |s: &str| Some(OsString::from(s));
+        assert_eq!(config_file_path(os("/xdg"), os("/home/me")),
+                   Some(PathBuf::from("/xdg/aoc/session")));
+        assert_eq!(config_file_path(os(""), os("/home/me")),
+                   Some(PathBuf::from("/home/me/.config/aoc/session")));
+        assert_eq!(config_file_path(None, os("/home/me")),
+                   Some(PathBuf::from("/home/me/.config/aoc/session")));
+        assert_eq!(config_file_path(None, None), None);
+    }
+
+    fn random_session_token() -> String {
+        let charset = "0123456789abcdef".as_bytes();
+        let mut rng = rand::thread_rng();
+        (0..96)
+            .map(|_| charset[rng.gen_range(0, charset.len())] as char)
+            .collect()
+    }
+}
@@ -0,0 +1,157 @@
+use crate::puzzle_input_fetcher::InputOrigin;
+use std::time::{Duration, Instant};
+
+// Runs the function the given number of times (at least once), returning the result of the last
+// run along with the time taken by each run
+pub fn measure<T, F: FnMut() -> T>(iterations: usize, mut f: F) -> (T, Vec<Duration>) {
+    let mut samples = Vec::with_capacity(iterations.max(1));
+    loop {
+        let start = Instant::now();
+        let result = f();
+        samples.push(start.elapsed());
+        if samples.len() >= iterations {
+            return (result, samples);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    // Summarizes a non-empty set of samples
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        // For an even number of samples this is the mean of the two middle samples
+        let median = (sorted[(sorted.len() - 1) / 2] + sorted[sorted.len() / 2]) / 2;
+        Stats {
+            median,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+// The time spent in each phase of solving a single day. Phases that were not run (or failed) are
+// left empty.
+pub struct DayTiming {
+    pub day: u8,
+
+    // Where the input came from, which makes all the difference to how long fetching it took
+    pub origin: InputOrigin,
+
+    // Fetching is only measured once: repeating it would only measure the in-memory cache
+    pub fetch: Duration,
+
+    pub parse: Option<Stats>,
+    pub part_1: Option<Stats>,
+    pub part_2: Option<Stats>,
+}
+
+impl DayTiming {
+    // The time to solve the day from scratch, using the median time of each phase
+    pub fn total(&self) -> Duration {
+        self.fetch + [self.parse, self.part_1, self.part_2].iter()
+            .flatten()
+            .map(|stats| stats.median)
+            .sum::<Duration>()
+    }
+}
+
+pub fn format_report(timings: &[DayTiming], iterations: usize) -> String {
+    let mut lines = vec![
+        format!("Timings over {} iteration(s) (median / min / max), except for fetching the input \
+                 which is measured once (along with where the input came from)", iterations),
+        format!("{:<5}{:<20}{:<32}{:<32}{:<32}{}",
+                "Day", "Fetch (once)", "Parse", "Part 1", "Part 2", "Total"),
+    ];
+    for timing in timings {
+        lines.push(format!("{:<5}{:<20}{:<32}{:<32}{:<32}{}",
+                           format!("{:02}", timing.day),
+                           format!("{} ({})", format_duration(timing.fetch), timing.origin),
+                           format_stats(timing.parse),
+                           format_stats(timing.part_1),
+                           format_stats(timing.part_2),
+                           format_duration(timing.total())));
+    }
+    let total = timings.iter().map(DayTiming::total).sum::<Duration>();
+    lines.push(format!("{:<121}{}", "Total", format_duration(total)));
+    lines.join("\n")
+}
+
+fn format_stats(stats: Option<Stats>) -> String {
+    match stats {
+        Some(stats) => format!("{} / {} / {}",
+                               format_duration(stats.median),
+                               format_duration(stats.min),
+                               format_duration(stats.max)),
+        None => "-".to_string(),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1?}", duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzle_input_fetcher::InputOrigin;
+    use crate::timing::{measure, format_report, DayTiming, Stats};
+    use std::time::Duration;
+
+    #[test]
+    fn test_measure_runs_the_requested_number_of_iterations() {
+        let mut runs = 0;
+        let (result, samples) = measure(5, || { runs += 1; runs });
+        assert_eq!(result, 5);
+        assert_eq!(samples.len(), 5);
+        let (result, samples) = measure(0, || "once");
+        assert_eq!(result, "once");
+        assert_eq!(samples.len(), 1);
+    }
+
+    #[test]
+    fn test_stats() {
+        let samples = [5, 1, 3].iter().map(|ms| Duration::from_millis(*ms)).collect::<Vec<_>>();
+        assert_eq!(Stats::from_samples(&samples), Stats {
+            median: Duration::from_millis(3),
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(5),
+        });
+        let samples = [4, 1, 2, 8].iter().map(|ms| Duration::from_millis(*ms)).collect::<Vec<_>>();
+        assert_eq!(Stats::from_samples(&samples).median, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn test_report_totals_the_medians() {
+        let stats = |ms| Some(Stats {
+            median: Duration::from_millis(ms),
+            min: Duration::from_millis(ms - 1),
+            max: Duration::from_millis(ms + 1),
+        });
+        let timings = vec![
+            DayTiming { day: 1, origin: InputOrigin::Local, fetch: Duration::from_millis(1),
+                        parse: stats(2), part_1: stats(3), part_2: stats(4) },
+            DayTiming { day: 2, origin: InputOrigin::Remote, fetch: Duration::from_millis(10),
+                        parse: stats(2), part_1: stats(3), part_2: None },
+        ];
+        assert_eq!(timings[0].total(), Duration::from_millis(10));
+        assert_eq!(timings[1].total(), Duration::from_millis(15));
+        let report = format_report(&timings, 3);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("Timings over 3 iteration(s)"));
+        assert!(lines[1].starts_with("Day  Fetch (once)"));
+        assert!(lines[2].starts_with("01   1.0ms (local)"));
+        assert!(lines[2].contains("2.0ms / 1.0ms / 3.0ms"));
+        assert!(lines[3].starts_with("02   10.0ms (remote)"));
+        assert!(lines[3].contains(" - "));
+        assert!(lines[4].starts_with("Total"));
+        assert!(lines[4].ends_with("25.0ms"));
+    }
+}